@@ -0,0 +1,805 @@
+use rust_scraper_core::adapter::{ExchangeAdapter, Rotation};
+use rust_scraper_core::book::OrderBook;
+use rust_scraper_core::de::{de_float_from_str, de_levels_from_str, de_opt_float_from_str};
+use rust_scraper_core::model::{
+    canonical_symbol, perpetual_symbol, BookDelta, BookLevel, BookSnapshot, FundingRate, Liquidation, MarkPrice, MarketEvent,
+    Quote, Side, Trade, Venue,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::symbols_or;
+
+/// `@bookTicker`; futures also stamp it with the transaction time `T`.
+#[derive(Serialize, Deserialize, Debug)]
+struct BinanceMessageQuote {
+    u: u64, // 42572951956 order book update id
+    #[serde(rename = "T", default)]
+    transaction_time: Option<i64>, // 1568014460891
+    s: String, // BTCUSDT
+    #[serde(deserialize_with = "de_float_from_str")]
+    b: f64, // 43244.39000000 bid price
+    #[serde(rename="B", deserialize_with = "de_float_from_str")]
+    bid_size: f64, // 0.02441000 bid vol
+    #[serde(deserialize_with = "de_float_from_str")]
+    a: f64, // 43244.39000000 ask price
+    #[serde(rename="A", deserialize_with = "de_float_from_str")]
+    ask_size: f64 // 0.02441000 ask vol
+}
+
+/// `@depth@100ms` diff, e.g.
+/// `{"e":"depthUpdate","E":1708270510698,"s":"BTCUSDT","U":157,"u":160,"b":[["43244.39","0.5"]],"a":[]}`.
+#[derive(Serialize, Deserialize, Debug)]
+struct BinanceMessageDepth {
+    #[serde(rename = "E")]
+    event_time: i64, // 1708270510698
+    s: String, // BTCUSDT
+    #[serde(rename = "U")]
+    first_update_id: u64, // 157
+    u: u64, // 160 final update id
+    #[serde(deserialize_with = "de_levels_from_str")]
+    b: Vec<BookLevel>,
+    #[serde(deserialize_with = "de_levels_from_str")]
+    a: Vec<BookLevel>,
+}
+
+/// `@aggTrade` or `@trade`, e.g.
+/// `{"e":"aggTrade","E":1708270510698,"s":"BTCUSDT","a":26129,"p":"43244.39","q":"0.02","f":100,"l":105,"T":1708270510695,"m":true,"M":true}`.
+/// Aggregated trades carry their id in `a`, raw trades in `t`.
+#[derive(Serialize, Deserialize, Debug)]
+struct BinanceMessageTrade {
+    e: String, // aggTrade, trade
+    s: String, // BTCUSDT
+    #[serde(default)]
+    a: Option<u64>, // 26129 aggregate trade id
+    #[serde(default)]
+    t: Option<u64>, // 12345 trade id
+    #[serde(deserialize_with = "de_float_from_str")]
+    p: f64, // 43244.39000000
+    #[serde(deserialize_with = "de_float_from_str")]
+    q: f64, // 0.02000000
+    #[serde(rename = "T")]
+    trade_time: i64, // 1708270510695
+    m: bool, // buyer is the maker
+}
+
+/// Futures `@markPrice@1s`, e.g.
+/// `{"e":"markPriceUpdate","E":1562305380000,"s":"BTCUSDT","p":"11794.15","i":"11784.62","P":"11784.25","r":"0.00038167","T":1562306400000}`.
+/// Dated futures have no funding, `r` is empty and `T` 0.
+#[derive(Serialize, Deserialize, Debug)]
+struct BinanceMessageMarkPrice {
+    #[serde(rename = "E")]
+    event_time: i64, // 1562305380000
+    s: String, // BTCUSDT
+    #[serde(deserialize_with = "de_float_from_str")]
+    p: f64, // 11794.15 mark price
+    #[serde(deserialize_with = "de_opt_float_from_str")]
+    i: Option<f64>, // 11784.62 index price
+    #[serde(deserialize_with = "de_opt_float_from_str")]
+    r: Option<f64>, // 0.00038167 funding rate
+    #[serde(rename = "T")]
+    next_funding_time: i64, // 1562306400000
+}
+
+/// Futures `@forceOrder`, the liquidation order in `o`, e.g.
+/// `{"e":"forceOrder","E":1568014460893,"o":{"s":"BTCUSDT","S":"SELL","o":"LIMIT","f":"IOC","q":"0.014","p":"9910","ap":"9910","X":"FILLED","l":"0.014","z":"0.014","T":1568014460893}}`.
+#[derive(Serialize, Deserialize, Debug)]
+struct BinanceMessageForceOrder {
+    o: BinanceForceOrder,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BinanceForceOrder {
+    s: String, // BTCUSDT
+    #[serde(rename = "S")]
+    side: String, // SELL
+    #[serde(deserialize_with = "de_float_from_str")]
+    ap: f64, // 9910 average fill price
+    #[serde(deserialize_with = "de_float_from_str")]
+    z: f64, // 0.014 filled quantity
+    #[serde(rename = "T")]
+    trade_time: i64, // 1568014460893
+}
+
+/// `GET /api/v3/depth` response.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BinanceDepthSnapshot {
+    last_update_id: u64,
+    #[serde(deserialize_with = "de_levels_from_str")]
+    bids: Vec<BookLevel>,
+    #[serde(deserialize_with = "de_levels_from_str")]
+    asks: Vec<BookLevel>,
+}
+
+/// Reply to a SUBSCRIBE request, e.g. `{"result":null,"id":1}`.
+#[derive(Serialize, Deserialize, Debug)]
+struct BinanceResponse {
+    result: Option<serde_json::Value>,
+    id: u64
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum BinanceMessage {
+    Depth(BinanceMessageDepth),
+    MarkPrice(BinanceMessageMarkPrice),
+    ForceOrder(BinanceMessageForceOrder),
+    Trade(BinanceMessageTrade),
+    Quote(BinanceMessageQuote),
+    Response(BinanceResponse)
+}
+
+/// What the combined endpoint sends: every stream message wrapped as
+/// `{"stream":"btcusdt@bookTicker","data":{...}}`, replies as they are.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum BinanceFrame {
+    Stream { stream: String, data: BinanceMessage },
+    Message(BinanceMessage),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BinanceSubscriptionMessage {
+    method: String,
+    params: Vec<String>,
+    id: u64
+}
+
+const BINANCE_WS_API: &str = "wss://stream.binance.com:9443/ws";
+const BINANCE_WS_API_COMBINED: &str = "wss://stream.binance.com:9443/stream";
+const BINANCE_FUTURES_WS_API_COMBINED: &str = "wss://fstream.binance.com/stream";
+
+/// Spot or USDⓈ-M futures; each has its own endpoint, limits and symbols.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Market {
+    Spot,
+    UsdM,
+}
+
+impl Market {
+    fn combined_endpoint(&self) -> &'static str {
+        match self {
+            Market::Spot => BINANCE_WS_API_COMBINED,
+            Market::UsdM => BINANCE_FUTURES_WS_API_COMBINED,
+        }
+    }
+
+    /// Most streams Binance allows on one connection.
+    fn max_streams(&self) -> usize {
+        match self {
+            Market::Spot => 1024,
+            Market::UsdM => 200,
+        }
+    }
+
+    /// Futures get their own series next to spot: `BTCUSDT` becomes
+    /// `BTC-USDT-PERP` and the quarterly `BTCUSDT_240628` `BTC-USDT-240628`.
+    fn symbol(&self, symbol: &str) -> String {
+        match self {
+            Market::Spot => canonical_symbol(symbol),
+            Market::UsdM => match symbol.split_once('_') {
+                Some((pair, expiry)) => format!("{}-{}", canonical_symbol(pair), expiry),
+                None => perpetual_symbol(symbol),
+            },
+        }
+    }
+
+    /// Stream name of a channel, `None` if the market doesn't have it.
+    fn stream(&self, channel: &str) -> Option<&'static str> {
+        match (self, channel) {
+            (_, "quote") => Some("bookTicker"),
+            (_, "trade") => Some("aggTrade"),
+            (Market::Spot, "book") => Some("depth@100ms"),
+            (Market::Spot, "raw-trade") => Some("trade"),
+            (Market::UsdM, "mark") => Some("markPrice@1s"),
+            (Market::UsdM, "liquidation") => Some("forceOrder"),
+            _ => None,
+        }
+    }
+}
+
+/// Binance closes connections after 24 hours, replace them a little earlier.
+fn rotation() -> Option<Rotation> {
+    Some(Rotation {
+        after: Duration::from_secs(23 * 60 * 60),
+        overlap: Duration::from_secs(30),
+    })
+}
+
+/// Ids remembered per stream; far more than the frames in flight on a
+/// connection when its replacement starts delivering.
+const SEEN_WINDOW: usize = 4096;
+
+/// Recent ids per stream, so messages both connections deliver while
+/// rotating are only kept once. A window rather than the highest id: the old
+/// connection may still deliver ids below the replacement's first one, and
+/// nothing else will deliver those. Ids older than the whole window count as
+/// seen.
+#[derive(Default)]
+struct Seen(HashMap<String, BTreeSet<u64>>);
+
+impl Seen {
+    fn first(&mut self, stream: String, id: u64) -> bool {
+        let ids = self.0.entry(stream).or_default();
+        if ids.len() >= SEEN_WINDOW && ids.first().is_some_and(|oldest| id < *oldest) {
+            return false;
+        }
+        if !ids.insert(id) {
+            return false;
+        }
+        if ids.len() > SEEN_WINDOW {
+            ids.pop_first();
+        }
+        true
+    }
+}
+
+const BINANCE_REST_API: &str = "https://api.binance.com/api/v3";
+
+/// Levels in the REST snapshot; 1000 is the most that costs the lower weight.
+const SNAPSHOT_LIMIT: u32 = 1000;
+
+/// Wait before fetching again after a failed or too old snapshot.
+const SNAPSHOT_RETRY: Duration = Duration::from_secs(1);
+
+const DEFAULT_SYMBOL: &str = "BTCUSDT";
+
+/// SUBSCRIBE request for `stream` on every symbol, e.g. `btcusdt@bookTicker`.
+fn subscription(symbols: &[String], stream: &str) -> String {
+    subscribe(symbols.iter().map(|symbol| stream_name(symbol, stream)).collect())
+}
+
+fn stream_name(symbol: &str, stream: &str) -> String {
+    format!("{}@{}", symbol.to_lowercase(), stream)
+}
+
+/// One SUBSCRIBE request for all of `params`; being a single message it
+/// stays well inside Binance's limit of 5 incoming messages per second.
+fn subscribe(params: Vec<String>) -> String {
+    let subscription = BinanceSubscriptionMessage {
+        method: String::from("SUBSCRIBE"),
+        params,
+        id: 1
+    };
+    serde_json::to_string::<BinanceSubscriptionMessage>(&subscription).unwrap()
+}
+
+/// `@bookTicker` top of book. The spot stream carries no timestamp so only `local_ts` is set.
+pub struct BinanceQuote {
+    symbols: Vec<String>,
+    seen: Seen,
+}
+
+impl BinanceQuote {
+    pub fn new(symbols: &[String]) -> Self {
+        BinanceQuote { symbols: symbols_or(symbols, DEFAULT_SYMBOL), seen: Seen::default() }
+    }
+}
+
+impl ExchangeAdapter for BinanceQuote {
+    fn exchange(&self) -> &str {
+        Venue::Binance.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        BINANCE_WS_API.to_string()
+    }
+
+    fn rotation(&self) -> Option<Rotation> {
+        rotation()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        vec![subscription(&self.symbols, "bookTicker")]
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        match serde_json::from_str(message)? {
+            BinanceMessage::Quote(quote) => Ok(quote_event(Market::Spot, quote, local_ts, &mut self.seen).into_iter().collect()),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+fn quote_event(market: Market, quote: BinanceMessageQuote, local_ts: i64, seen: &mut Seen) -> Option<MarketEvent> {
+    if !seen.first(format!("{}@bookTicker", quote.s), quote.u) {
+        return None;
+    }
+    Some(MarketEvent::Quote(Quote {
+        venue: Venue::Binance,
+        symbol: market.symbol(&quote.s),
+        exchange_ts: quote.transaction_time,
+        local_ts,
+        bid_price: quote.b,
+        bid_size: quote.bid_size,
+        ask_price: quote.a,
+        ask_size: quote.ask_size,
+    }))
+}
+
+async fn fetch_snapshot(client: &reqwest::Client, symbol: &str) -> Result<BinanceDepthSnapshot, String> {
+    let url = format!("{}/depth?symbol={}&limit={}", BINANCE_REST_API, symbol.to_uppercase(), SNAPSHOT_LIMIT);
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let body = response.error_for_status().map_err(|e| e.to_string())?.text().await.map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| e.to_string())
+}
+
+/// Where one symbol's local book is in the sync procedure.
+enum DepthState {
+    /// Diffs are kept until the REST snapshot arrives.
+    Buffering(Vec<BinanceMessageDepth>),
+    /// The book is current up to update id `last_update_id`.
+    Synced { last_update_id: u64 },
+}
+
+/// One symbol's book. While buffering it holds the last synced book, so the
+/// levels the next snapshot drops can be written as removed.
+#[derive(Default)]
+struct Depth {
+    book: OrderBook,
+    state: Option<DepthState>,
+}
+
+/// Full depth book kept in sync the way Binance documents it: diffs from
+/// `@depth@100ms` are buffered while a REST snapshot is fetched, the ones the
+/// snapshot already covers are dropped and the rest applied on top. Each diff
+/// must then start right after the previous one ended (`U` <= last `u` + 1),
+/// otherwise the book is dropped and synced again.
+pub struct BinanceBook {
+    symbols: Vec<String>,
+    client: reqwest::Client,
+    depths: HashMap<String, Depth>,
+    snapshots: mpsc::UnboundedSender<(String, BinanceDepthSnapshot)>,
+    snapshots_rx: mpsc::UnboundedReceiver<(String, BinanceDepthSnapshot)>,
+}
+
+impl BinanceBook {
+    pub fn new(symbols: &[String]) -> Self {
+        let (snapshots, snapshots_rx) = mpsc::unbounded_channel();
+        BinanceBook {
+            symbols: symbols_or(symbols, DEFAULT_SYMBOL),
+            client: reqwest::Client::builder().timeout(Duration::from_secs(10)).build().expect("Can't build HTTP client."),
+            depths: HashMap::new(),
+            snapshots,
+            snapshots_rx,
+        }
+    }
+
+    /// Starts buffering `symbol` and fetches its snapshot in the background,
+    /// after `delay` and retrying until one arrives.
+    fn resync(&mut self, symbol: &str, delay: Duration) {
+        self.depths.entry(symbol.to_string()).or_default().state = Some(DepthState::Buffering(Vec::new()));
+        let client = self.client.clone();
+        let snapshots = self.snapshots.clone();
+        let symbol = symbol.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            while !snapshots.is_closed() {
+                match fetch_snapshot(&client, &symbol).await {
+                    Ok(snapshot) => {
+                        info!(symbol = %symbol, last_update_id = snapshot.last_update_id, "Fetched depth snapshot");
+                        let _ = snapshots.send((symbol, snapshot));
+                        return;
+                    }
+                    Err(e) => {
+                        warn!(symbol = %symbol, error = %e, "Can't fetch depth snapshot");
+                        tokio::time::sleep(SNAPSHOT_RETRY).await;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Applies a snapshot to the diffs buffered for its symbol.
+    fn snapshot(&mut self, symbol: String, snapshot: BinanceDepthSnapshot, local_ts: i64) -> Vec<MarketEvent> {
+        let Some(depth) = self.depths.get_mut(&symbol) else {
+            return Vec::new();
+        };
+        let Some(DepthState::Buffering(buffered)) = depth.state.take() else {
+            return Vec::new();
+        };
+        // The snapshot has to reach at least the first diff we buffered.
+        if buffered.first().is_some_and(|first| snapshot.last_update_id < first.first_update_id) {
+            warn!(symbol = %symbol, last_update_id = snapshot.last_update_id, "Depth snapshot older than buffered diffs");
+            self.resync(&symbol, SNAPSHOT_RETRY);
+            return Vec::new();
+        }
+        let mut book = OrderBook::new();
+        book.replace(&snapshot.bids, &snapshot.asks);
+        let mut last_update_id = snapshot.last_update_id;
+        let mut exchange_ts = None;
+        for diff in buffered.iter().filter(|diff| diff.u > snapshot.last_update_id) {
+            // The first diff applied has to straddle the snapshot, later ones follow on.
+            if diff.first_update_id > last_update_id + 1 {
+                warn!(symbol = %symbol, expected = last_update_id + 1, first_update_id = diff.first_update_id, "Depth diffs don't follow snapshot");
+                self.resync(&symbol, SNAPSHOT_RETRY);
+                return Vec::new();
+            }
+            apply(&mut book, diff);
+            last_update_id = diff.u;
+            exchange_ts = Some(diff.event_time);
+        }
+        let depth = self.depths.entry(symbol.clone()).or_default();
+        depth.state = Some(DepthState::Synced { last_update_id });
+        depth.book.snapshot_events(BookSnapshot {
+            venue: Venue::Binance,
+            symbol: canonical_symbol(&symbol),
+            exchange_ts,
+            local_ts,
+            bids: book.bids(),
+            asks: book.asks(),
+        })
+    }
+
+    /// Applies any snapshots fetched since the last message, then `diff`.
+    fn events(&mut self, diff: Option<BinanceMessageDepth>, local_ts: i64) -> Vec<MarketEvent> {
+        let mut events = Vec::new();
+        while let Ok((symbol, snapshot)) = self.snapshots_rx.try_recv() {
+            events.extend(self.snapshot(symbol, snapshot, local_ts));
+        }
+        if let Some(diff) = diff {
+            events.extend(self.depth(diff, local_ts));
+        }
+        events
+    }
+
+    fn depth(&mut self, diff: BinanceMessageDepth, local_ts: i64) -> Vec<MarketEvent> {
+        let symbol = diff.s.clone();
+        let depth = self.depths.get_mut(&symbol);
+        match depth.and_then(|depth| depth.state.as_mut().map(|state| (&mut depth.book, state))) {
+            Some((_, DepthState::Buffering(buffered))) => {
+                buffered.push(diff);
+                Vec::new()
+            }
+            Some((book, DepthState::Synced { last_update_id })) => {
+                // Already applied, e.g. sent by both connections while rotating.
+                // Diffs overlapping what was applied are fine, levels are absolute.
+                if diff.u <= *last_update_id {
+                    return Vec::new();
+                }
+                // Right after a snapshot the diff may straddle it, so only a jump counts.
+                if diff.first_update_id > *last_update_id + 1 {
+                    warn!(symbol = %symbol, expected = *last_update_id + 1, first_update_id = diff.first_update_id, "Depth update id gap, resyncing");
+                    self.resync(&symbol, Duration::ZERO);
+                    return self.depth(diff, local_ts);
+                }
+                apply(book, &diff);
+                *last_update_id = diff.u;
+                let levels = diff.b.iter().map(|level| (Side::Buy, level)).chain(diff.a.iter().map(|level| (Side::Sell, level)));
+                levels
+                    .map(|(side, level)| {
+                        MarketEvent::BookDelta(BookDelta {
+                            venue: Venue::Binance,
+                            symbol: canonical_symbol(&symbol),
+                            exchange_ts: Some(diff.event_time),
+                            local_ts,
+                            side,
+                            price: level.price,
+                            size: level.size,
+                        })
+                    })
+                    .collect()
+            }
+            // First diff for the symbol, start the sync.
+            None => {
+                self.resync(&symbol, Duration::ZERO);
+                self.depth(diff, local_ts)
+            }
+        }
+    }
+}
+
+fn apply(book: &mut OrderBook, diff: &BinanceMessageDepth) {
+    for level in &diff.b {
+        book.update(Side::Buy, level.price, level.size);
+    }
+    for level in &diff.a {
+        book.update(Side::Sell, level.price, level.size);
+    }
+}
+
+impl ExchangeAdapter for BinanceBook {
+    fn exchange(&self) -> &str {
+        Venue::Binance.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        BINANCE_WS_API.to_string()
+    }
+
+    fn rotation(&self) -> Option<Rotation> {
+        rotation()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        vec![subscription(&self.symbols, "depth@100ms")]
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let diff = match serde_json::from_str(message)? {
+            BinanceMessage::Depth(diff) => Some(diff),
+            _ => None,
+        };
+        Ok(self.events(diff, local_ts))
+    }
+}
+
+/// Trades from `@aggTrade` or `@trade`, stamped with the trade time `T`.
+pub struct BinanceTrade {
+    symbols: Vec<String>,
+    stream: &'static str,
+    seen: Seen,
+}
+
+impl BinanceTrade {
+    /// Aggregated trades, one per taker order and price.
+    pub fn aggregated(symbols: &[String]) -> Self {
+        BinanceTrade { symbols: symbols_or(symbols, DEFAULT_SYMBOL), stream: "aggTrade", seen: Seen::default() }
+    }
+
+    /// Every individual fill.
+    pub fn raw(symbols: &[String]) -> Self {
+        BinanceTrade { symbols: symbols_or(symbols, DEFAULT_SYMBOL), stream: "trade", seen: Seen::default() }
+    }
+}
+
+impl ExchangeAdapter for BinanceTrade {
+    fn exchange(&self) -> &str {
+        Venue::Binance.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        BINANCE_WS_API.to_string()
+    }
+
+    fn rotation(&self) -> Option<Rotation> {
+        rotation()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        vec![subscription(&self.symbols, self.stream)]
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        match serde_json::from_str(message)? {
+            BinanceMessage::Trade(trade) => Ok(trade_event(Market::Spot, trade, local_ts, &mut self.seen).into_iter().collect()),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+fn trade_event(market: Market, trade: BinanceMessageTrade, local_ts: i64, seen: &mut Seen) -> Option<MarketEvent> {
+    let trade_id = trade.a.or(trade.t);
+    if trade_id.is_some_and(|id| !seen.first(format!("{}@{}", trade.s, trade.e), id)) {
+        return None;
+    }
+    Some(MarketEvent::Trade(Trade {
+        venue: Venue::Binance,
+        symbol: market.symbol(&trade.s),
+        exchange_ts: Some(trade.trade_time),
+        local_ts,
+        // A maker buyer means the seller took liquidity.
+        side: if trade.m { Side::Sell } else { Side::Buy },
+        price: trade.p,
+        size: trade.q,
+        trade_id: trade_id.map(|id| id.to_string()),
+    }))
+}
+
+fn mark_events(market: Market, mark: BinanceMessageMarkPrice, local_ts: i64, seen: &mut Seen) -> Vec<MarketEvent> {
+    if !seen.first(format!("{}@markPrice", mark.s), mark.event_time as u64) {
+        return Vec::new();
+    }
+    let symbol = market.symbol(&mark.s);
+    let mut events = vec![MarketEvent::MarkPrice(MarkPrice {
+        venue: Venue::Binance,
+        symbol: symbol.clone(),
+        exchange_ts: Some(mark.event_time),
+        local_ts,
+        mark_price: mark.p,
+        index_price: mark.i,
+    })];
+    if let Some(rate) = mark.r {
+        events.push(MarketEvent::FundingRate(FundingRate {
+            venue: Venue::Binance,
+            symbol,
+            exchange_ts: Some(mark.event_time),
+            local_ts,
+            rate,
+            next_funding_ts: Some(mark.next_funding_time).filter(|ts| *ts > 0),
+        }));
+    }
+    events
+}
+
+/// Binance pushes at most one liquidation per symbol a second, so the trade
+/// time is enough to tell repeats apart.
+fn liquidation_event(market: Market, order: BinanceForceOrder, local_ts: i64, seen: &mut Seen) -> Option<MarketEvent> {
+    if !seen.first(format!("{}@forceOrder", order.s), order.trade_time as u64) {
+        return None;
+    }
+    let Some(side) = Side::parse(&order.side) else {
+        warn!(side = %order.side, "Unknown side");
+        return None;
+    };
+    Some(MarketEvent::Liquidation(Liquidation {
+        venue: Venue::Binance,
+        symbol: market.symbol(&order.s),
+        exchange_ts: Some(order.trade_time),
+        local_ts,
+        side,
+        price: order.ap,
+        size: order.z,
+    }))
+}
+
+/// Several channels for many symbols over one connection to the combined
+/// endpoint, each message routed to the channel it belongs to.
+pub struct BinanceCombined {
+    market: Market,
+    symbols: Vec<String>,
+    streams: Vec<&'static str>,
+    book: Option<BinanceBook>,
+    seen: Seen,
+}
+
+impl BinanceCombined {
+    /// `channels` are the names of the single channel feeds: `quote`, `trade`
+    /// and on spot `book` and `raw-trade`, on futures `mark` (mark price and
+    /// funding) and `liquidation`. Fails if one isn't available on `market`
+    /// or there would be no streams, or more than one connection may carry.
+    pub fn new(market: Market, channels: &[String], symbols: &[String]) -> Result<Self, String> {
+        let symbols = symbols_or(symbols, DEFAULT_SYMBOL);
+        let mut streams = Vec::new();
+        let mut book = None;
+        for channel in channels {
+            let Some(stream) = market.stream(channel) else {
+                return Err(format!("channel {:?} isn't available on {:?}", channel, market));
+            };
+            if channel == "book" {
+                book = Some(BinanceBook::new(&symbols));
+            }
+            streams.push(stream);
+        }
+        let count = streams.len() * symbols.len();
+        if count == 0 || count > market.max_streams() {
+            return Err(format!(
+                "{} streams ({} channels for {} symbols), a connection carries 1 to {}",
+                count,
+                streams.len(),
+                symbols.len(),
+                market.max_streams()
+            ));
+        }
+        Ok(BinanceCombined { market, symbols, streams, book, seen: Seen::default() })
+    }
+}
+
+impl ExchangeAdapter for BinanceCombined {
+    fn exchange(&self) -> &str {
+        Venue::Binance.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        self.market.combined_endpoint().to_string()
+    }
+
+    fn rotation(&self) -> Option<Rotation> {
+        rotation()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        let params = self
+            .symbols
+            .iter()
+            .flat_map(|symbol| self.streams.iter().map(move |stream| stream_name(symbol, stream)))
+            .collect();
+        vec![subscribe(params)]
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let message = match serde_json::from_str(message)? {
+            BinanceFrame::Stream { data, .. } => data,
+            BinanceFrame::Message(message) => message,
+        };
+        let (market, seen) = (self.market, &mut self.seen);
+        let events = match (message, &mut self.book) {
+            (BinanceMessage::Quote(quote), _) => quote_event(market, quote, local_ts, seen).into_iter().collect(),
+            (BinanceMessage::Trade(trade), _) => trade_event(market, trade, local_ts, seen).into_iter().collect(),
+            (BinanceMessage::MarkPrice(mark), _) => mark_events(market, mark, local_ts, seen),
+            (BinanceMessage::ForceOrder(order), _) => liquidation_event(market, order.o, local_ts, seen).into_iter().collect(),
+            (BinanceMessage::Depth(diff), Some(book)) => book.events(Some(diff), local_ts),
+            (BinanceMessage::Depth(_), None) | (BinanceMessage::Response(_), _) => Vec::new(),
+        };
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_ids_from_the_old_connection_are_kept_during_rotation() {
+        let mut seen = Seen::default();
+        let stream = || "btcusdt@aggTrade".to_string();
+        assert!(seen.first(stream(), 10));
+        // The replacement's first frame is read before the old connection's
+        // 11 and 12, which only it delivers.
+        assert!(seen.first(stream(), 13));
+        assert!(seen.first(stream(), 11));
+        assert!(seen.first(stream(), 12));
+        // Then both deliver the same ids.
+        assert!(!seen.first(stream(), 13));
+        assert!(seen.first(stream(), 14));
+        assert!(!seen.first(stream(), 14));
+        assert!(seen.first("ethusdt@aggTrade".to_string(), 11));
+    }
+
+    #[test]
+    fn ids_older_than_the_window_count_as_seen() {
+        let mut seen = Seen::default();
+        for id in 100..100 + SEEN_WINDOW as u64 {
+            assert!(seen.first("btcusdt@trade".to_string(), id));
+        }
+        assert!(!seen.first("btcusdt@trade".to_string(), 99));
+        assert!(seen.first("btcusdt@trade".to_string(), 100 + SEEN_WINDOW as u64));
+        assert!(!seen.first("btcusdt@trade".to_string(), 100));
+    }
+
+    #[test]
+    fn combined_rejects_channels_it_cant_carry() {
+        let channels = |channels: &[&str]| channels.iter().map(|channel| channel.to_string()).collect::<Vec<_>>();
+        let error = BinanceCombined::new(Market::Spot, &channels(&["quote", "mark"]), &[]).err().unwrap();
+        assert_eq!(error, r#"channel "mark" isn't available on Spot"#);
+        assert!(BinanceCombined::new(Market::Spot, &[], &[]).is_err());
+        let symbols: Vec<String> = (0..201).map(|i| format!("COIN{}USDT", i)).collect();
+        assert!(BinanceCombined::new(Market::UsdM, &channels(&["quote"]), &symbols).is_err());
+        assert!(BinanceCombined::new(Market::UsdM, &channels(&["quote"]), &symbols[..200]).is_ok());
+    }
+
+    fn diff(first_update_id: u64, u: u64) -> BinanceMessageDepth {
+        let diff = format!(
+            r#"{{"e":"depthUpdate","E":1708270510698,"s":"BTCUSDT","U":{},"u":{},"b":[["43244.39","0.5"]],"a":[]}}"#,
+            first_update_id, u
+        );
+        serde_json::from_str(&diff).unwrap()
+    }
+
+    fn snapshot(last_update_id: u64) -> BinanceDepthSnapshot {
+        let snapshot =
+            format!(r#"{{"lastUpdateId":{},"bids":[["43244.00","1.0"]],"asks":[["43245.00","2.0"]]}}"#, last_update_id);
+        serde_json::from_str(&snapshot).unwrap()
+    }
+
+    fn buffering(book: &BinanceBook) -> bool {
+        matches!(book.depths["BTCUSDT"].state, Some(DepthState::Buffering(_)))
+    }
+
+    #[tokio::test]
+    async fn snapshot_older_than_buffered_diffs_is_fetched_again() {
+        let mut book = BinanceBook::new(&[]);
+        assert_eq!(book.events(Some(diff(157, 160)), 1), Vec::new());
+        assert_eq!(book.snapshot("BTCUSDT".to_string(), snapshot(150), 2), Vec::new());
+        assert!(buffering(&book));
+        // The refetched one straddles the diffs buffered since.
+        book.events(Some(diff(161, 163)), 3);
+        assert!(!book.snapshot("BTCUSDT".to_string(), snapshot(162), 4).is_empty());
+        assert!(matches!(book.depths["BTCUSDT"].state, Some(DepthState::Synced { last_update_id: 163 })));
+    }
+
+    #[tokio::test]
+    async fn update_id_gap_resyncs() {
+        let mut book = BinanceBook::new(&[]);
+        book.events(Some(diff(157, 160)), 1);
+        book.snapshot("BTCUSDT".to_string(), snapshot(158), 2);
+        assert_eq!(book.events(Some(diff(161, 164)), 3).len(), 1);
+        // 165 went missing.
+        assert_eq!(book.events(Some(diff(166, 170)), 4), Vec::new());
+        assert!(buffering(&book));
+    }
+}
@@ -0,0 +1,768 @@
+use rust_scraper_core::adapter::ExchangeAdapter;
+use rust_scraper_core::book::OrderBook;
+use rust_scraper_core::model::{
+    canonical_symbol, BookDelta, BookLevel, BookSnapshot, BookTop, DepthBand, FundingRate, Instrument, Liquidation,
+    MarketEvent, Quote, Side, Trade, Venue,
+};
+use rust_scraper_core::time::parse_millis;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{debug, info, warn};
+
+use super::symbols_or;
+
+/// An `orderBookL2` level. `update` and `delete` identify the level by `id`
+/// and may leave out its price, side and timestamp.
+#[derive(Serialize, Deserialize, Debug)]
+struct BitmexMessagePriceLevel {
+    symbol: String,
+    id: i64,
+    side: Option<String>,
+    size: Option<i64>,
+    price: Option<f64>,
+    timestamp: Option<String> // "2024-01-03T00:09:50.444Z"
+}
+
+/// An `orderBook10` row: the top ten levels a side as `[price, size]`.
+#[derive(Serialize, Deserialize, Debug)]
+struct BitmexMessageBook10 {
+    symbol: String,
+    bids: Vec<[f64; 2]>,
+    asks: Vec<[f64; 2]>,
+    timestamp: String // "2024-01-03T00:09:50.444Z"
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BitmexMessageQuote {
+    symbol: String,
+    timestamp: String, // 2024-01-03T00:09:50.444Z
+    bid_price: f64,
+    bid_size: f64,
+    ask_price: f64,
+    ask_size: f64
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BitmexMessageTrade {
+    symbol: String,
+    // id: i64,
+    side: String,
+    size: f64,
+    price: f64,
+    timestamp: String, // 2024-01-03T00:09:50.444Z
+    tick_direction: String, // ZeroPlusTick, ZeroMinusTick, PlusTick, MinusTick
+    trd_match_i_d: String, // 00000000-006d-1000-0000-00043aec045f
+    gross_value: i64, // 7658820
+    home_notional: f64, // 0.0765882
+    foreign_notional: f64, // 3000.0
+    trd_type: String // Regular
+}
+
+/// An `instrument` row. Only `partial` has every field, updates carry the
+/// ones that changed.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BitmexMessageInstrument {
+    symbol: String,
+    timestamp: Option<String>, // 2024-01-03T00:09:50.444Z
+    mark_price: Option<f64>,
+    indicative_settle_price: Option<f64>, // the index price
+    open_interest: Option<f64>, // contracts
+    funding_rate: Option<f64>, // 0.0001, for the next funding
+    fair_basis: Option<f64>
+}
+
+/// A `funding` row, one per symbol at every funding time.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BitmexMessageFunding {
+    symbol: String,
+    timestamp: String, // 2024-01-03T04:00:00.000Z
+    funding_rate: f64,
+    funding_interval: Option<String>, // 2000-01-01T08:00:00.000Z, i.e. 8 hours
+    funding_rate_daily: Option<f64>
+}
+
+/// A `liquidation` row, an open liquidation order. `update` may leave out
+/// everything but the order id and what changed.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BitmexMessageLiquidation {
+    #[serde(rename = "orderID")]
+    order_id: String,
+    symbol: String,
+    side: Option<String>,
+    price: Option<f64>,
+    leaves_qty: Option<f64>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BitmexMessageFilter {
+    account: Option<i32>,
+    symbol: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BitmexMessage<T> {
+    table: String,
+    action: String,
+    data: Vec<T>,
+    keys: Option<Vec<String>>,
+    types: Option<HashMap<String, String>>,
+    filter: Option<BitmexMessageFilter>
+}
+
+/// Frames about the connection rather than a table: the welcome sent on
+/// connect, one ack per subscribed topic and request errors.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum BitmexResponse {
+    Error {
+        status: Option<u16>,
+        error: String
+    },
+    Subscribed {
+        success: bool,
+        subscribe: Option<String>
+    },
+    Info {
+        info: String,
+        version: Option<String>
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum BitmexFrame<T> {
+    Message(BitmexMessage<T>),
+    Response(BitmexResponse),
+}
+
+const BITMEX_WS_API: &str = "wss://ws.bitmex.com/realtime";
+
+const DEFAULT_SYMBOL: &str = "XBTUSD";
+
+/// Depth bands written with the book's top when a feed sets no `bands_bps`.
+const DEFAULT_BANDS_BPS: [u32; 3] = [10, 25, 50];
+
+/// Realtime URL subscribing to `table` for every symbol.
+fn endpoint(table: &str, symbols: &[String]) -> String {
+    let topics: Vec<String> = symbols.iter().map(|symbol| format!("{}:{}", table, symbol)).collect();
+    format!("{}?subscribe={}", BITMEX_WS_API, topics.join(","))
+}
+
+/// Parses a table message, logging frames about the connection itself and
+/// returning `None` for those.
+fn table_message<T: DeserializeOwned>(message: &str) -> Result<Option<BitmexMessage<T>>, serde_json::Error> {
+    let response = match serde_json::from_str(message)? {
+        BitmexFrame::Message(message) => return Ok(Some(message)),
+        BitmexFrame::Response(response) => response,
+    };
+    match response {
+        BitmexResponse::Info { info, version } => info!(info = %info, version = ?version, "Welcome"),
+        BitmexResponse::Subscribed { success: true, subscribe } => info!(subscribe = ?subscribe, "Subscribed"),
+        BitmexResponse::Subscribed { success: false, subscribe } => warn!(subscribe = ?subscribe, "Subscription failed"),
+        BitmexResponse::Error { status, error } => warn!(status = ?status, error = %error, "Request failed"),
+    }
+    Ok(None)
+}
+
+fn parse_side(side: &str) -> Option<Side> {
+    let parsed = Side::parse(side);
+    if parsed.is_none() {
+        warn!(side, "Unknown side");
+    }
+    parsed
+}
+
+/// Symbols a book message changed, with the timestamp it gave each.
+type Changed = Vec<(String, Option<i64>)>;
+
+/// The book tables BitMEX publishes, from the full book to the lightest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookTable {
+    /// `orderBookL2`, every level.
+    L2,
+    /// `orderBookL2_25`, the top 25 levels a side with the same updates as `L2`.
+    L2_25,
+    /// `orderBook10`, the top 10 levels a side sent whole on every change.
+    Top10,
+}
+
+impl BookTable {
+    fn table(&self) -> &'static str {
+        match self {
+            BookTable::L2 => "orderBookL2",
+            BookTable::L2_25 => "orderBookL2_25",
+            BookTable::Top10 => "orderBook10",
+        }
+    }
+}
+
+/// One symbol's book. BitMEX names L2 levels by id, so the side and price of
+/// every level are kept to resolve `update` and `delete`.
+#[derive(Default)]
+struct LocalBook {
+    levels: HashMap<i64, (Side, f64)>,
+    book: OrderBook,
+    /// The top of book last written.
+    top: Option<BookTop>,
+}
+
+/// Book levels, one series per side and price, plus the top of book and
+/// depth bands derived from them whenever those change. With `Top10` only the
+/// levels that changed are written and a level that leaves the top ten is
+/// written as size 0.
+pub struct BitmexBook {
+    table: BookTable,
+    symbols: Vec<String>,
+    bands_bps: Vec<u32>,
+    books: HashMap<String, LocalBook>,
+}
+
+impl BitmexBook {
+    pub fn new(table: BookTable, symbols: &[String], bands_bps: &[u32]) -> Self {
+        BitmexBook {
+            table,
+            symbols: symbols_or(symbols, DEFAULT_SYMBOL),
+            bands_bps: if bands_bps.is_empty() { DEFAULT_BANDS_BPS.to_vec() } else { bands_bps.to_vec() },
+            books: HashMap::new(),
+        }
+    }
+
+    /// The top of `symbol`'s book, if it changed since the last one.
+    fn top(&mut self, symbol: &str, exchange_ts: Option<i64>, local_ts: i64) -> Option<MarketEvent> {
+        let local = self.books.get_mut(symbol)?;
+        let (bid, ask) = (local.book.best_bid()?, local.book.best_ask()?);
+        let mid = (bid.price + ask.price) / 2.0;
+        let bands: Vec<DepthBand> = self
+            .bands_bps
+            .iter()
+            .map(|&bps| {
+                let (bid_size, ask_size) = local.book.depth_within(mid, bps);
+                DepthBand { bps, bid_size, ask_size }
+            })
+            .collect();
+        if let Some(last) = &local.top {
+            if last.bid == bid && last.ask == ask && last.bands == bands {
+                return None;
+            }
+        }
+        let top = BookTop {
+            venue: Venue::Bitmex,
+            symbol: canonical_symbol(symbol),
+            exchange_ts,
+            local_ts,
+            bid,
+            ask,
+            mid,
+            spread: ask.price - bid.price,
+            bands,
+        };
+        local.top = Some(top.clone());
+        Some(MarketEvent::BookTop(top))
+    }
+
+    fn snapshot(&mut self, data: &[BitmexMessagePriceLevel], local_ts: i64) -> Vec<MarketEvent> {
+        let mut by_symbol: HashMap<&str, Vec<&BitmexMessagePriceLevel>> = HashMap::new();
+        for price_level in data {
+            by_symbol.entry(&price_level.symbol).or_default().push(price_level);
+        }
+        let mut events = Vec::new();
+        for (symbol, price_levels) in by_symbol {
+            let local = self.books.entry(symbol.to_string()).or_default();
+            local.levels.clear();
+            let mut bids = Vec::new();
+            let mut asks = Vec::new();
+            for price_level in &price_levels {
+                let (Some(side), Some(price), Some(size)) =
+                    (price_level.side.as_deref().and_then(parse_side), price_level.price, price_level.size)
+                else {
+                    warn!(symbol, id = price_level.id, "Incomplete level in partial");
+                    continue;
+                };
+                local.levels.insert(price_level.id, (side, price));
+                let level = BookLevel { price, size: size as f64 };
+                match side {
+                    Side::Buy => bids.push(level),
+                    Side::Sell => asks.push(level),
+                }
+            }
+            events.extend(local.book.snapshot_events(BookSnapshot {
+                venue: Venue::Bitmex,
+                symbol: canonical_symbol(symbol),
+                exchange_ts: price_levels
+                    .iter()
+                    .filter_map(|level| parse_millis(level.timestamp.as_deref()?))
+                    .max(),
+                local_ts,
+                bids,
+                asks,
+            }));
+        }
+        events
+    }
+
+    /// Applies `orderBook10` rows, each the whole top of a symbol's book.
+    fn top10(&mut self, data: &[BitmexMessageBook10], local_ts: i64) -> Vec<MarketEvent> {
+        let levels = |levels: &[[f64; 2]]| -> Vec<BookLevel> {
+            levels.iter().map(|[price, size]| BookLevel { price: *price, size: *size }).collect()
+        };
+        let mut events = Vec::new();
+        for row in data {
+            let local = self.books.entry(row.symbol.clone()).or_default();
+            events.extend(local.book.diff_events(BookSnapshot {
+                venue: Venue::Bitmex,
+                symbol: canonical_symbol(&row.symbol),
+                exchange_ts: parse_millis(&row.timestamp),
+                local_ts,
+                bids: levels(&row.bids),
+                asks: levels(&row.asks),
+            }));
+        }
+        events
+    }
+
+    /// Parses an `orderBookL2` or `orderBookL2_25` message.
+    fn l2(&mut self, message: &str, local_ts: i64) -> Result<(Vec<MarketEvent>, Changed), serde_json::Error> {
+        let Some(payload) = table_message::<BitmexMessagePriceLevel>(message)? else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        let events = match payload.action.as_str() {
+            "partial" => self.snapshot(&payload.data, local_ts),
+            action @ ("insert" | "update" | "delete") => payload
+                .data
+                .iter()
+                .filter_map(|price_level| self.apply(action, price_level, local_ts))
+                .collect(),
+            _ => {
+                warn!(action = %payload.action, "Unknown action key value");
+                return Ok((Vec::new(), Vec::new()));
+            }
+        };
+        let changed = payload
+            .data
+            .iter()
+            .map(|price_level| {
+                (price_level.symbol.clone(), price_level.timestamp.as_deref().and_then(parse_millis))
+            })
+            .collect();
+        Ok((events, changed))
+    }
+
+    /// Applies an `insert`, `update` or `delete` of one level, resolving its
+    /// side and price from the id when the message leaves them out.
+    fn apply(&mut self, action: &str, price_level: &BitmexMessagePriceLevel, local_ts: i64) -> Option<MarketEvent> {
+        let Some(local) = self.books.get_mut(&price_level.symbol) else {
+            warn!(symbol = %price_level.symbol, id = price_level.id, action, "Level before partial");
+            return None;
+        };
+        let known = local.levels.get(&price_level.id).copied();
+        let side = match price_level.side.as_deref() {
+            Some(side) => parse_side(side),
+            None => known.map(|(side, _)| side),
+        };
+        let (Some(side), Some(price)) = (side, price_level.price.or(known.map(|(_, price)| price))) else {
+            warn!(symbol = %price_level.symbol, id = price_level.id, action, "Unknown level id");
+            return None;
+        };
+        let size = match action {
+            "delete" => {
+                local.levels.remove(&price_level.id);
+                0
+            }
+            _ => {
+                let Some(size) = price_level.size else {
+                    warn!(symbol = %price_level.symbol, id = price_level.id, "Size is None");
+                    return None;
+                };
+                local.levels.insert(price_level.id, (side, price));
+                size
+            }
+        };
+        local.book.update(side, price, size as f64);
+        Some(MarketEvent::BookDelta(BookDelta {
+            venue: Venue::Bitmex,
+            symbol: canonical_symbol(&price_level.symbol),
+            exchange_ts: price_level.timestamp.as_deref().and_then(parse_millis),
+            local_ts,
+            side,
+            price,
+            size: size as f64,
+        }))
+    }
+}
+
+impl ExchangeAdapter for BitmexBook {
+    fn exchange(&self) -> &str {
+        Venue::Bitmex.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        endpoint(self.table.table(), &self.symbols)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let (mut events, changed) = match self.table {
+            BookTable::Top10 => {
+                let Some(payload) = table_message::<BitmexMessageBook10>(message)? else {
+                    return Ok(Vec::new());
+                };
+                let changed = payload.data.iter().map(|row| (row.symbol.clone(), parse_millis(&row.timestamp))).collect();
+                (self.top10(&payload.data, local_ts), changed)
+            }
+            BookTable::L2 | BookTable::L2_25 => self.l2(message, local_ts)?,
+        };
+        // Once per message, so the top never reflects half an update.
+        let mut latest: BTreeMap<String, Option<i64>> = BTreeMap::new();
+        for (symbol, exchange_ts) in changed {
+            let latest = latest.entry(symbol).or_default();
+            *latest = (*latest).max(exchange_ts);
+        }
+        for (symbol, exchange_ts) in latest {
+            events.extend(self.top(&symbol, exchange_ts, local_ts));
+        }
+        Ok(events)
+    }
+}
+
+/// `quote` top of book.
+pub struct BitmexQuote {
+    symbols: Vec<String>,
+}
+
+impl BitmexQuote {
+    pub fn new(symbols: &[String]) -> Self {
+        BitmexQuote { symbols: symbols_or(symbols, DEFAULT_SYMBOL) }
+    }
+}
+
+impl ExchangeAdapter for BitmexQuote {
+    fn exchange(&self) -> &str {
+        Venue::Bitmex.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        endpoint("quote", &self.symbols)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let Some(payload) = table_message::<BitmexMessageQuote>(message)? else {
+            return Ok(Vec::new());
+        };
+        let events = match payload.action.as_str() {
+            "partial" | "update" | "insert" => payload
+                .data
+                .iter()
+                .map(|quote| {
+                    MarketEvent::Quote(Quote {
+                        venue: Venue::Bitmex,
+                        symbol: canonical_symbol(&quote.symbol),
+                        exchange_ts: parse_millis(&quote.timestamp),
+                        local_ts,
+                        bid_price: quote.bid_price,
+                        bid_size: quote.bid_size,
+                        ask_price: quote.ask_price,
+                        ask_size: quote.ask_size,
+                    })
+                })
+                .collect(),
+            "delete" => {
+                debug!(payload = ?payload, "Received delete");
+                Vec::new()
+            }
+            _ => {
+                warn!(action = %payload.action, "Unknown action key value");
+                Vec::new()
+            }
+        };
+        Ok(events)
+    }
+}
+
+/// `trade` prints, one price and volume series per aggressor side.
+pub struct BitmexTrade {
+    symbols: Vec<String>,
+}
+
+impl BitmexTrade {
+    pub fn new(symbols: &[String]) -> Self {
+        BitmexTrade { symbols: symbols_or(symbols, DEFAULT_SYMBOL) }
+    }
+}
+
+impl ExchangeAdapter for BitmexTrade {
+    fn exchange(&self) -> &str {
+        Venue::Bitmex.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        endpoint("trade", &self.symbols)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let Some(payload) = table_message::<BitmexMessageTrade>(message)? else {
+            return Ok(Vec::new());
+        };
+        let events = match payload.action.as_str() {
+            "partial" | "update" | "insert" => payload
+                .data
+                .iter()
+                .filter_map(|trade| {
+                    Some(MarketEvent::Trade(Trade {
+                        venue: Venue::Bitmex,
+                        symbol: canonical_symbol(&trade.symbol),
+                        exchange_ts: Some(parse_millis(&trade.timestamp)?),
+                        local_ts,
+                        side: parse_side(&trade.side)?,
+                        price: trade.price,
+                        size: trade.size,
+                        trade_id: Some(trade.trd_match_i_d.clone()),
+                    }))
+                })
+                .collect(),
+            "delete" => {
+                debug!(payload = ?payload, "Received delete");
+                Vec::new()
+            }
+            _ => {
+                warn!(action = %payload.action, "Unknown action key value");
+                Vec::new()
+            }
+        };
+        Ok(events)
+    }
+}
+
+/// `instrument` mark and index price, open interest, funding rate and fair
+/// basis, each under `INSTRUMENT:*`.
+pub struct BitmexInstrument {
+    symbols: Vec<String>,
+}
+
+impl BitmexInstrument {
+    pub fn new(symbols: &[String]) -> Self {
+        BitmexInstrument { symbols: symbols_or(symbols, DEFAULT_SYMBOL) }
+    }
+}
+
+impl ExchangeAdapter for BitmexInstrument {
+    fn exchange(&self) -> &str {
+        Venue::Bitmex.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        endpoint("instrument", &self.symbols)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let Some(payload) = table_message::<BitmexMessageInstrument>(message)? else {
+            return Ok(Vec::new());
+        };
+        let events = match payload.action.as_str() {
+            "partial" | "update" | "insert" => payload
+                .data
+                .iter()
+                .map(|instrument| Instrument {
+                    venue: Venue::Bitmex,
+                    symbol: canonical_symbol(&instrument.symbol),
+                    exchange_ts: instrument.timestamp.as_deref().and_then(parse_millis),
+                    local_ts,
+                    mark_price: instrument.mark_price,
+                    index_price: instrument.indicative_settle_price,
+                    open_interest: instrument.open_interest,
+                    funding_rate: instrument.funding_rate,
+                    fair_basis: instrument.fair_basis,
+                })
+                // Most updates only move fields that aren't written, e.g. lastPrice.
+                .filter(|instrument| {
+                    [
+                        instrument.mark_price,
+                        instrument.index_price,
+                        instrument.open_interest,
+                        instrument.funding_rate,
+                        instrument.fair_basis,
+                    ]
+                    .iter()
+                    .any(Option::is_some)
+                })
+                .map(MarketEvent::Instrument)
+                .collect(),
+            "delete" => {
+                debug!(payload = ?payload, "Received delete");
+                Vec::new()
+            }
+            _ => {
+                warn!(action = %payload.action, "Unknown action key value");
+                Vec::new()
+            }
+        };
+        Ok(events)
+    }
+}
+
+/// `funding` rates as they're charged, under `FUNDING:RATE`.
+pub struct BitmexFunding {
+    symbols: Vec<String>,
+}
+
+impl BitmexFunding {
+    pub fn new(symbols: &[String]) -> Self {
+        BitmexFunding { symbols: symbols_or(symbols, DEFAULT_SYMBOL) }
+    }
+}
+
+impl ExchangeAdapter for BitmexFunding {
+    fn exchange(&self) -> &str {
+        Venue::Bitmex.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        endpoint("funding", &self.symbols)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let Some(payload) = table_message::<BitmexMessageFunding>(message)? else {
+            return Ok(Vec::new());
+        };
+        let events = match payload.action.as_str() {
+            // The partial is the last funding, rewriting it is harmless.
+            "partial" | "insert" => payload
+                .data
+                .iter()
+                .map(|funding| {
+                    MarketEvent::FundingRate(FundingRate {
+                        venue: Venue::Bitmex,
+                        symbol: canonical_symbol(&funding.symbol),
+                        exchange_ts: parse_millis(&funding.timestamp),
+                        local_ts,
+                        rate: funding.funding_rate,
+                        next_funding_ts: None,
+                    })
+                })
+                .collect(),
+            "update" | "delete" => {
+                debug!(payload = ?payload, "Received funding change");
+                Vec::new()
+            }
+            _ => {
+                warn!(action = %payload.action, "Unknown action key value");
+                Vec::new()
+            }
+        };
+        Ok(events)
+    }
+}
+
+/// `liquidation` orders as they're placed, under `LIQUIDATION:{SIDE}:*`.
+pub struct BitmexLiquidation {
+    symbols: Vec<String>,
+}
+
+impl BitmexLiquidation {
+    pub fn new(symbols: &[String]) -> Self {
+        BitmexLiquidation { symbols: symbols_or(symbols, DEFAULT_SYMBOL) }
+    }
+}
+
+impl ExchangeAdapter for BitmexLiquidation {
+    fn exchange(&self) -> &str {
+        Venue::Bitmex.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        endpoint("liquidation", &self.symbols)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let Some(payload) = table_message::<BitmexMessageLiquidation>(message)? else {
+            return Ok(Vec::new());
+        };
+        let events = match payload.action.as_str() {
+            "insert" => payload
+                .data
+                .iter()
+                .filter_map(|liquidation| {
+                    let (Some(side), Some(price), Some(size)) = (liquidation.side.as_deref(), liquidation.price, liquidation.leaves_qty)
+                    else {
+                        warn!(order_id = %liquidation.order_id, "Incomplete liquidation");
+                        return None;
+                    };
+                    Some(MarketEvent::Liquidation(Liquidation {
+                        venue: Venue::Bitmex,
+                        symbol: canonical_symbol(&liquidation.symbol),
+                        exchange_ts: None,
+                        local_ts,
+                        side: parse_side(side)?,
+                        price,
+                        size,
+                    }))
+                })
+                .collect(),
+            // The partial lists orders that were placed before we connected,
+            // updates and deletes are them being worked and filled.
+            "partial" | "update" | "delete" => {
+                debug!(payload = ?payload, "Received liquidation change");
+                Vec::new()
+            }
+            _ => {
+                warn!(action = %payload.action, "Unknown action key value");
+                Vec::new()
+            }
+        };
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_frames_are_not_parse_failures() {
+        let frames = [
+            r#"{"info":"Welcome to the BitMEX Realtime API.","version":"2.0.0","timestamp":"2024-01-03T00:09:50.444Z","docs":"https://www.bitmex.com/app/wsAPI","heartbeatEnabled":false,"limit":{"remaining":39}}"#,
+            r#"{"success":true,"subscribe":"quote:XBTUSD","request":{"op":"subscribe","args":["quote:XBTUSD"]}}"#,
+            r#"{"status":400,"error":"Unknown table: quotes","meta":{},"request":{"op":"subscribe","args":["quotes:XBTUSD"]}}"#,
+        ];
+        let mut adapters: Vec<Box<dyn ExchangeAdapter>> = vec![
+            Box::new(BitmexBook::new(BookTable::L2, &[], &[])),
+            Box::new(BitmexBook::new(BookTable::Top10, &[], &[])),
+            Box::new(BitmexQuote::new(&[])),
+            Box::new(BitmexTrade::new(&[])),
+            Box::new(BitmexInstrument::new(&[])),
+            Box::new(BitmexFunding::new(&[])),
+            Box::new(BitmexLiquidation::new(&[])),
+        ];
+        for adapter in &mut adapters {
+            for frame in frames {
+                assert_eq!(adapter.parse(frame, 1).unwrap(), Vec::new(), "{}", frame);
+            }
+        }
+    }
+
+    #[test]
+    fn update_and_delete_resolve_price_from_level_id() {
+        let mut book = BitmexBook::new(BookTable::L2, &[], &[]);
+        book.parse(r#"{"table":"orderBookL2","action":"partial","data":[{"symbol":"XBTUSD","id":8799,"side":"Buy","size":100,"price":42000.5,"timestamp":"2024-01-03T00:09:50.444Z"},{"symbol":"XBTUSD","id":8800,"side":"Sell","size":50,"price":42001.0,"timestamp":"2024-01-03T00:09:50.444Z"}]}"#, 1).unwrap();
+        let deltas = |events: Vec<MarketEvent>| -> Vec<(Side, f64, f64)> {
+            events
+                .into_iter()
+                .filter_map(|event| match event {
+                    MarketEvent::BookDelta(delta) => Some((delta.side, delta.price, delta.size)),
+                    _ => None,
+                })
+                .collect()
+        };
+        let update = book.parse(r#"{"table":"orderBookL2","action":"update","data":[{"symbol":"XBTUSD","id":8799,"size":250,"timestamp":"2024-01-03T00:09:51.000Z"}]}"#, 2).unwrap();
+        assert_eq!(deltas(update), vec![(Side::Buy, 42000.5, 250.0)]);
+        let delete = book.parse(r#"{"table":"orderBookL2","action":"delete","data":[{"symbol":"XBTUSD","id":8800}]}"#, 3).unwrap();
+        assert_eq!(deltas(delete), vec![(Side::Sell, 42001.0, 0.0)]);
+        // Once deleted the id is unknown again.
+        let delete = book.parse(r#"{"table":"orderBookL2","action":"delete","data":[{"symbol":"XBTUSD","id":8800}]}"#, 4).unwrap();
+        assert_eq!(deltas(delete), Vec::new());
+    }
+}
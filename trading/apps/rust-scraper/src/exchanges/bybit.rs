@@ -0,0 +1,540 @@
+use rust_scraper_core::adapter::{ExchangeAdapter, Heartbeat};
+use rust_scraper_core::book::OrderBook;
+use rust_scraper_core::de::{de_float_from_str, de_levels_from_str, de_opt_float_from_str};
+use rust_scraper_core::feed::FeedCommand;
+use rust_scraper_core::model::{
+    canonical_symbol, perpetual_symbol, BookDelta, BookLevel, BookSnapshot, MarketEvent, Quote, Side, Ticker, Trade, Venue,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use super::symbols_or;
+
+/// Spot tickers are always snapshots, other categories send deltas with only
+/// the changed fields, hence everything being optional.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BybitMessageTickerData {
+    symbol: String, // BTCUSDT
+    #[serde(default, deserialize_with = "de_opt_float_from_str")]
+    last_price: Option<f64>, // "51820.29"
+    #[serde(default, deserialize_with = "de_opt_float_from_str")]
+    high_price24h: Option<f64>, // "52071.86"
+    #[serde(default, deserialize_with = "de_opt_float_from_str")]
+    low_price24h: Option<f64>, // "50736.61"
+    #[serde(default, deserialize_with = "de_opt_float_from_str")]
+    prev_price24h: Option<f64>, // "51050"
+    #[serde(default, deserialize_with = "de_opt_float_from_str")]
+    volume24h: Option<f64>, // "6749.938786"
+    #[serde(default, deserialize_with = "de_opt_float_from_str")]
+    turnover24h: Option<f64>, // "348026142.20831225"
+    #[serde(default, deserialize_with = "de_opt_float_from_str")]
+    price24h_pcnt: Option<f64>, // "0.0151"
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BybitMessageOrderbookData {
+    s: String, // BTCUSDT
+    #[serde(deserialize_with = "de_levels_from_str")]
+    b: Vec<BookLevel>, // [["16493.50", "0.006"]]
+    #[serde(deserialize_with = "de_levels_from_str")]
+    a: Vec<BookLevel>, // [["16611.00", "0.029"]]
+    u: u64, // 18521288 update id
+    seq: u64, // 7961638724
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BybitMessageTradeData {
+    #[serde(rename = "T")]
+    t: i64, // 1672304486865 trade time
+    s: String, // BTCUSDT
+    #[serde(rename = "S")]
+    side: String, // Buy, Sell
+    #[serde(deserialize_with = "de_float_from_str")]
+    v: f64, // "0.001"
+    #[serde(deserialize_with = "de_float_from_str")]
+    p: f64, // "16578.50"
+    i: String, // "20f43950-d8dd-5b31-9112-a178eb6023af"
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BybitMessage {
+    topic: String, // tickers.BTCUSDT
+    ts: i64, // 1708270510698
+    r#type: String, // snapshot, delta
+    data: serde_json::Value,
+}
+
+/// Reply to an `op` request, e.g.
+/// `{"success":true,"ret_msg":"subscribe","conn_id":"...","op":"subscribe"}`.
+#[derive(Serialize, Deserialize, Debug)]
+struct BybitResponse {
+    op: String, // subscribe, ping
+    #[serde(default)]
+    success: Option<bool>,
+    #[serde(default)]
+    ret_msg: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum BybitFrame {
+    Message(BybitMessage),
+    Response(BybitResponse),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BybitSubscriptionMessage {
+    op: String,
+    args: Vec<String>
+}
+
+const BYBIT_WS_API: &str = "wss://stream.bybit.com/v5/public/spot";
+const BYBIT_WS_API_LINEAR: &str = "wss://stream.bybit.com/v5/public/linear";
+
+/// Levels per side on the book feed; 50 is available for both spot and linear.
+const BOOK_DEPTH: u32 = 50;
+
+const DEFAULT_SYMBOL: &str = "BTCUSDT";
+
+/// Topics Bybit accepts in one subscribe request on spot.
+const MAX_ARGS: usize = 10;
+
+/// Bybit drops connections that haven't sent `{"op":"ping"}` for 20 seconds.
+fn heartbeat() -> Heartbeat {
+    Heartbeat::Text {
+        interval: Duration::from_secs(20),
+        message: r#"{"op":"ping"}"#.to_string(),
+    }
+}
+
+fn subscription(args: Vec<String>) -> String {
+    request("subscribe", args)
+}
+
+/// Subscribe requests for `args`, split to stay within `MAX_ARGS` each.
+fn subscriptions(args: Vec<String>) -> Vec<String> {
+    args.chunks(MAX_ARGS).map(|args| subscription(args.to_vec())).collect()
+}
+
+fn request(op: &str, args: Vec<String>) -> String {
+    let request = BybitSubscriptionMessage {
+        op: op.to_string(),
+        args
+    };
+    serde_json::to_string::<BybitSubscriptionMessage>(&request).unwrap()
+}
+
+/// Logs failed requests; acks and pongs carry nothing else of interest.
+fn response(response: &BybitResponse) {
+    match response.success {
+        Some(false) => warn!(op = %response.op, ret_msg = ?response.ret_msg, "Request failed"),
+        _ => debug!(op = %response.op, ret_msg = ?response.ret_msg, "Response"),
+    }
+}
+
+/// Best bid and ask as last seen on `orderbook.1`.
+#[derive(Default)]
+struct Top {
+    bid: Option<BookLevel>,
+    ask: Option<BookLevel>,
+}
+
+/// Applies a level 1 update to one side: a snapshot replaces it, a delta
+/// with size 0 removes the level at that price.
+fn apply_top(side: &mut Option<BookLevel>, levels: &[BookLevel], snapshot: bool) {
+    if snapshot {
+        *side = levels.first().copied();
+        return;
+    }
+    for level in levels {
+        if level.size > 0.0 {
+            *side = Some(*level);
+        } else if side.is_some_and(|current| current.price == level.price) {
+            *side = None;
+        }
+    }
+}
+
+/// Best bid/ask from `orderbook.1` and last price from `tickers`; spot tickers
+/// don't carry the top of book.
+pub struct BybitQuote {
+    symbols: Vec<String>,
+    tops: HashMap<String, Top>,
+    last_prices: HashMap<String, f64>,
+}
+
+impl BybitQuote {
+    pub fn new(symbols: &[String]) -> Self {
+        BybitQuote {
+            symbols: symbols_or(symbols, DEFAULT_SYMBOL),
+            tops: HashMap::new(),
+            last_prices: HashMap::new(),
+        }
+    }
+
+    fn ticker(&mut self, message: &BybitMessage, data: BybitMessageTickerData, local_ts: i64) -> Option<MarketEvent> {
+        // Deltas without a price change still carry the last one we saw.
+        let last_price = match data.last_price {
+            Some(last_price) => {
+                self.last_prices.insert(data.symbol.clone(), last_price);
+                last_price
+            }
+            None => *self.last_prices.get(&data.symbol)?,
+        };
+        Some(MarketEvent::Ticker(Ticker {
+            venue: Venue::Bybit,
+            symbol: canonical_symbol(&data.symbol),
+            exchange_ts: Some(message.ts),
+            local_ts,
+            last_price,
+            volume_24h: data.volume24h,
+        }))
+    }
+
+    fn top(&mut self, message: &BybitMessage, data: BybitMessageOrderbookData, local_ts: i64) -> Option<MarketEvent> {
+        let snapshot = message.r#type == "snapshot";
+        let top = self.tops.entry(data.s.clone()).or_default();
+        apply_top(&mut top.bid, &data.b, snapshot);
+        apply_top(&mut top.ask, &data.a, snapshot);
+        let (bid, ask) = (top.bid?, top.ask?);
+        Some(MarketEvent::Quote(Quote {
+            venue: Venue::Bybit,
+            symbol: canonical_symbol(&data.s),
+            exchange_ts: Some(message.ts),
+            local_ts,
+            bid_price: bid.price,
+            bid_size: bid.size,
+            ask_price: ask.price,
+            ask_size: ask.size,
+        }))
+    }
+}
+
+impl ExchangeAdapter for BybitQuote {
+    fn exchange(&self) -> &str {
+        Venue::Bybit.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        BYBIT_WS_API.to_string()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        let args = self
+            .symbols
+            .iter()
+            .flat_map(|symbol| [format!("orderbook.1.{}", symbol), format!("tickers.{}", symbol)])
+            .collect();
+        subscriptions(args)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let mut message = match serde_json::from_str(message)? {
+            BybitFrame::Message(message) => message,
+            BybitFrame::Response(r) => {
+                response(&r);
+                return Ok(Vec::new());
+            }
+        };
+        let data = message.data.take();
+        let event = if message.topic.starts_with("orderbook.") {
+            self.top(&message, serde_json::from_value(data)?, local_ts)
+        } else if message.topic.starts_with("tickers.") {
+            self.ticker(&message, serde_json::from_value(data)?, local_ts)
+        } else {
+            warn!(topic = %message.topic, "Unknown topic");
+            None
+        };
+        Ok(event.into_iter().collect())
+    }
+
+    fn heartbeat(&self) -> Heartbeat {
+        heartbeat()
+    }
+}
+
+/// Which market a Bybit feed reads; they live on separate endpoints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    Spot,
+    Linear,
+}
+
+impl Category {
+    fn endpoint(&self) -> &'static str {
+        match self {
+            Category::Spot => BYBIT_WS_API,
+            Category::Linear => BYBIT_WS_API_LINEAR,
+        }
+    }
+
+    /// Linear perpetuals get their own series so they don't mix with spot.
+    /// Dated futures such as `BTC-27DEC24` keep their name.
+    fn symbol(&self, symbol: &str) -> String {
+        match self {
+            Category::Linear if !symbol.contains('-') => perpetual_symbol(symbol),
+            _ => canonical_symbol(symbol),
+        }
+    }
+}
+
+/// Local copy of one symbol's book and the update id it is at.
+#[derive(Default)]
+struct LocalBook {
+    book: OrderBook,
+    update_id: u64,
+    seq: u64,
+    /// False until a snapshot arrives; deltas before then are ignored.
+    synced: bool,
+}
+
+/// Full depth book from `orderbook.50` snapshots and deltas.
+///
+/// Every delta must carry the update id following the previous one. On a gap
+/// the book is dropped and the topic resubscribed, which makes Bybit send a
+/// fresh snapshot.
+pub struct BybitBook {
+    category: Category,
+    symbols: Vec<String>,
+    books: HashMap<String, LocalBook>,
+    commands: Vec<FeedCommand>,
+}
+
+impl BybitBook {
+    pub fn new(category: Category, symbols: &[String]) -> Self {
+        BybitBook {
+            category,
+            symbols: symbols_or(symbols, DEFAULT_SYMBOL),
+            books: HashMap::new(),
+            commands: Vec::new(),
+        }
+    }
+
+    fn resync(&mut self, topic: &str) {
+        self.commands.push(FeedCommand::Send(request("unsubscribe", vec![topic.to_string()])));
+        self.commands.push(FeedCommand::Send(subscription(vec![topic.to_string()])));
+    }
+
+    fn book(&mut self, message: &BybitMessage, data: BybitMessageOrderbookData, local_ts: i64) -> Vec<MarketEvent> {
+        let symbol = self.category.symbol(&data.s);
+        let local = self.books.entry(data.s.clone()).or_default();
+
+        // u = 1 means Bybit restarted the book, treat it as a snapshot.
+        if message.r#type == "snapshot" || data.u == 1 {
+            local.update_id = data.u;
+            local.seq = data.seq;
+            local.synced = true;
+            return local.book.snapshot_events(BookSnapshot {
+                venue: Venue::Bybit,
+                symbol,
+                exchange_ts: Some(message.ts),
+                local_ts,
+                bids: data.b,
+                asks: data.a,
+            });
+        }
+        if !local.synced {
+            debug!(topic = %message.topic, u = data.u, "Delta before snapshot");
+            return Vec::new();
+        }
+        if data.u != local.update_id + 1 || data.seq < local.seq {
+            warn!(
+                topic = %message.topic,
+                expected = local.update_id + 1,
+                u = data.u,
+                seq = data.seq,
+                last_seq = local.seq,
+                "Update id gap, resyncing"
+            );
+            // The stale book stays until the snapshot, to tell which levels it dropped.
+            local.synced = false;
+            self.resync(&message.topic);
+            return Vec::new();
+        }
+        local.update_id = data.u;
+        local.seq = data.seq;
+
+        let levels = data.b.iter().map(|level| (Side::Buy, level)).chain(data.a.iter().map(|level| (Side::Sell, level)));
+        levels
+            .map(|(side, level)| {
+                local.book.update(side, level.price, level.size);
+                MarketEvent::BookDelta(BookDelta {
+                    venue: Venue::Bybit,
+                    symbol: symbol.clone(),
+                    exchange_ts: Some(message.ts),
+                    local_ts,
+                    side,
+                    price: level.price,
+                    size: level.size,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ExchangeAdapter for BybitBook {
+    fn exchange(&self) -> &str {
+        Venue::Bybit.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        self.category.endpoint().to_string()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        let args = self.symbols.iter().map(|symbol| format!("orderbook.{}.{}", BOOK_DEPTH, symbol)).collect();
+        subscriptions(args)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let mut message = match serde_json::from_str(message)? {
+            BybitFrame::Message(message) => message,
+            BybitFrame::Response(r) => {
+                response(&r);
+                return Ok(Vec::new());
+            }
+        };
+        if !message.topic.starts_with("orderbook.") {
+            warn!(topic = %message.topic, "Unknown topic");
+            return Ok(Vec::new());
+        }
+        let data = serde_json::from_value(message.data.take())?;
+        Ok(self.book(&message, data, local_ts))
+    }
+
+    fn heartbeat(&self) -> Heartbeat {
+        heartbeat()
+    }
+
+    fn commands(&mut self) -> Vec<FeedCommand> {
+        std::mem::take(&mut self.commands)
+    }
+}
+
+/// Trades from `publicTrade`, stamped with the trade's own time rather than
+/// the message's.
+pub struct BybitTrade {
+    category: Category,
+    symbols: Vec<String>,
+}
+
+impl BybitTrade {
+    pub fn new(category: Category, symbols: &[String]) -> Self {
+        BybitTrade { category, symbols: symbols_or(symbols, DEFAULT_SYMBOL) }
+    }
+}
+
+impl ExchangeAdapter for BybitTrade {
+    fn exchange(&self) -> &str {
+        Venue::Bybit.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        self.category.endpoint().to_string()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        let args = self.symbols.iter().map(|symbol| format!("publicTrade.{}", symbol)).collect();
+        subscriptions(args)
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let mut message = match serde_json::from_str(message)? {
+            BybitFrame::Message(message) => message,
+            BybitFrame::Response(r) => {
+                response(&r);
+                return Ok(Vec::new());
+            }
+        };
+        if !message.topic.starts_with("publicTrade.") {
+            warn!(topic = %message.topic, "Unknown topic");
+            return Ok(Vec::new());
+        }
+        let trades: Vec<BybitMessageTradeData> = serde_json::from_value(message.data.take())?;
+        let events = trades
+            .into_iter()
+            .filter_map(|trade| {
+                let Some(side) = Side::parse(&trade.side) else {
+                    warn!(side = %trade.side, "Unknown side");
+                    return None;
+                };
+                Some(MarketEvent::Trade(Trade {
+                    venue: Venue::Bybit,
+                    symbol: self.category.symbol(&trade.s),
+                    exchange_ts: Some(trade.t),
+                    local_ts,
+                    side,
+                    price: trade.p,
+                    size: trade.v,
+                    trade_id: Some(trade.i),
+                }))
+            })
+            .collect();
+        Ok(events)
+    }
+
+    fn heartbeat(&self) -> Heartbeat {
+        heartbeat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(r#type: &str, u: u64, seq: u64, bids: &str) -> String {
+        format!(
+            r#"{{"topic":"orderbook.50.BTCUSDT","ts":1672304484978,"type":"{}","data":{{"s":"BTCUSDT","b":{},"a":[["16611.00","0.029"]],"u":{},"seq":{}}},"cts":1672304484976}}"#,
+            r#type, bids, u, seq
+        )
+    }
+
+    #[test]
+    fn subscribe_requests_stay_within_ten_topics() {
+        let symbols: Vec<String> = (0..6).map(|i| format!("COIN{}USDT", i)).collect();
+        let requests = BybitQuote::new(&symbols).subscriptions();
+        let args: Vec<usize> = requests
+            .iter()
+            .map(|request| serde_json::from_str::<BybitSubscriptionMessage>(request).unwrap().args.len())
+            .collect();
+        assert_eq!(args, vec![10, 2]);
+    }
+
+    #[test]
+    fn update_id_one_restarts_the_book() {
+        let mut book = BybitBook::new(Category::Spot, &[]);
+        book.parse(&frame("snapshot", 400, 7000, r#"[["16493.50","0.006"],["16493.00","0.100"]]"#), 1).unwrap();
+        assert_eq!(book.parse(&frame("delta", 401, 7001, r#"[["16493.50","0.008"]]"#), 2).unwrap().len(), 2);
+        // Sent as a delta, but it replaces the book and restarts the ids.
+        let events = book.parse(&frame("delta", 1, 7100, r#"[["16494.00","0.200"]]"#), 3).unwrap();
+        let removed: Vec<f64> = events
+            .iter()
+            .filter_map(|event| match event {
+                MarketEvent::BookDelta(delta) if delta.size == 0.0 => Some(delta.price),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(removed, vec![16493.0, 16493.5]);
+        assert!(matches!(events.last(), Some(MarketEvent::BookSnapshot(_))));
+        assert_eq!(book.parse(&frame("delta", 2, 7101, "[]"), 4).unwrap().len(), 1);
+        assert!(book.commands().is_empty());
+    }
+
+    #[test]
+    fn update_id_or_seq_gap_resubscribes() {
+        let mut book = BybitBook::new(Category::Spot, &[]);
+        book.parse(&frame("snapshot", 400, 7000, "[]"), 1).unwrap();
+        assert_eq!(book.parse(&frame("delta", 402, 7001, "[]"), 2).unwrap(), Vec::new());
+        let commands = book.commands();
+        assert!(matches!(&commands[..], [FeedCommand::Send(unsubscribe), FeedCommand::Send(subscribe)]
+            if unsubscribe.contains("unsubscribe") && subscribe.contains(r#""op":"subscribe""#)));
+        // Deltas are ignored until the snapshot that follows.
+        assert_eq!(book.parse(&frame("delta", 403, 7002, "[]"), 3).unwrap(), Vec::new());
+        book.parse(&frame("snapshot", 410, 7010, "[]"), 4).unwrap();
+        assert_eq!(book.parse(&frame("delta", 411, 7005, "[]"), 5).unwrap(), Vec::new());
+        assert_eq!(book.commands().len(), 2);
+    }
+}
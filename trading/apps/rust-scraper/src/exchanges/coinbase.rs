@@ -0,0 +1,435 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rust_scraper_core::adapter::{ExchangeAdapter, Subscriber};
+use rust_scraper_core::book::OrderBook;
+use rust_scraper_core::de::{de_float_from_str, de_levels_from_str};
+use rust_scraper_core::model::{
+    canonical_symbol, BookDelta, BookLevel, BookSnapshot, Gap, MarketEvent, Quote, Side, Ticker, Trade, Venue,
+};
+use rust_scraper_core::time::parse_millis;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+use super::symbols_or;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CoinbaseMessageQuote {
+    sequence: u64, // 37475248783,
+    product_id: String, // "ETH-USD",
+    #[serde(deserialize_with = "de_float_from_str")]
+    price: f64, // "1285.22",
+    open_24h: String, // "1310.79",
+    #[serde(deserialize_with = "de_float_from_str")]
+    volume_24h: f64, // "245532.79269678",
+    low_24h: String, // "1280.52",
+    high_24h: String, // "1313.8",
+    volume_30d: String, // "9788783.60117027",
+    #[serde(deserialize_with = "de_float_from_str")]
+    best_bid: f64, // "1285.04",
+    #[serde(deserialize_with = "de_float_from_str")]
+    best_bid_size: f64, // "0.46688654",
+    #[serde(deserialize_with = "de_float_from_str")]
+    best_ask: f64, // "1285.27",
+    #[serde(deserialize_with = "de_float_from_str")]
+    best_ask_size: f64, // "1.56637040",
+    side: String, // "buy",
+    time: String, // "2022-10-19T23:28:22.061769Z",
+    trade_id: u64, // 370843401,
+    #[serde(deserialize_with = "de_float_from_str")]
+    last_size: f64, // "11.4396987"
+  }
+
+/// `match`, or `last_match` for the most recent one right after subscribing.
+#[derive(Serialize, Deserialize, Debug)]
+struct CoinbaseMessageMatch {
+    trade_id: u64, // 10
+    sequence: u64, // 50
+    product_id: String, // "BTC-USD"
+    time: String, // "2014-11-07T08:19:27.028459Z"
+    #[serde(deserialize_with = "de_float_from_str")]
+    size: f64, // "5.23512"
+    #[serde(deserialize_with = "de_float_from_str")]
+    price: f64, // "400.23"
+    side: String, // "sell", the maker's side
+}
+
+/// Everything the feed sends, told apart by `type`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CoinbaseMessage {
+    Ticker(CoinbaseMessageQuote),
+    Match(CoinbaseMessageMatch),
+    LastMatch(CoinbaseMessageMatch),
+    /// `{"type":"snapshot","product_id":"BTC-USD","bids":[["10101.10","0.45054140"]],"asks":[["10102.55","0.57753524"]]}`
+    Snapshot {
+        product_id: String,
+        #[serde(deserialize_with = "de_levels_from_str")]
+        bids: Vec<BookLevel>,
+        #[serde(deserialize_with = "de_levels_from_str")]
+        asks: Vec<BookLevel>,
+    },
+    /// `{"type":"l2update","product_id":"BTC-USD","time":"2019-08-14T20:42:27.265Z","changes":[["buy","10101.80000000","0.162567"]]}`
+    L2update {
+        product_id: String,
+        time: String,
+        changes: Vec<(String, String, String)>,
+    },
+    Subscriptions {
+        channels: serde_json::Value,
+    },
+    Error {
+        message: String,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+}
+
+/// Logs replies about the subscription itself; `None` for market data.
+fn response(message: CoinbaseMessage) -> Option<CoinbaseMessage> {
+    match message {
+        CoinbaseMessage::Subscriptions { channels } => info!(channels = %channels, "Subscribed"),
+        CoinbaseMessage::Error { message, reason } => warn!(message = %message, reason = ?reason, "Subscription error"),
+        message => return Some(message),
+    }
+    None
+}
+
+/// Last sequence number seen per product. Coinbase numbers every message of
+/// a product across all channels, so on a single channel the numbers skip;
+/// they only tell when something arrived late or twice.
+#[derive(Default)]
+struct Sequences(HashMap<String, u64>);
+
+impl Sequences {
+    fn in_order(&mut self, product_id: &str, sequence: u64) -> bool {
+        let last = self.0.entry(product_id.to_string()).or_default();
+        if sequence <= *last {
+            debug!(product_id, sequence, last = *last, "Out of order message");
+            return false;
+        }
+        *last = sequence;
+        true
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CoinbaseSubscriptionMessage {
+    r#type: String,
+    channels: Vec<String>,
+    product_ids: Vec<String>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    auth: Option<CoinbaseAuth>,
+}
+
+/// Signature fields of an authenticated subscribe.
+#[derive(Serialize, Deserialize, Debug)]
+struct CoinbaseAuth {
+    signature: String,
+    key: String,
+    passphrase: String,
+    timestamp: String,
+}
+
+/// API key for authenticated channels, from `COINBASE_API_KEY`,
+/// `COINBASE_API_SECRET` and `COINBASE_API_PASSPHRASE`.
+#[derive(Clone)]
+struct Credentials {
+    key: String,
+    secret: Vec<u8>,
+    passphrase: String,
+}
+
+impl Credentials {
+    /// `None` unless all three variables are set; a secret that isn't base64
+    /// is logged and ignored.
+    fn from_env() -> Option<Self> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+        let (key, secret, passphrase) = (var("COINBASE_API_KEY")?, var("COINBASE_API_SECRET")?, var("COINBASE_API_PASSPHRASE")?);
+        match BASE64.decode(secret) {
+            Ok(secret) => Some(Credentials { key, secret, passphrase }),
+            Err(e) => {
+                warn!(error = %e, "COINBASE_API_SECRET isn't base64, subscribing unauthenticated");
+                None
+            }
+        }
+    }
+
+    /// Signs the websocket login: base64 HMAC-SHA256 of
+    /// `{timestamp}GET/users/self/verify` keyed with the decoded secret.
+    fn sign(&self) -> CoinbaseAuth {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs().to_string();
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC takes keys of any size");
+        mac.update(format!("{}GET/users/self/verify", timestamp).as_bytes());
+        CoinbaseAuth {
+            signature: BASE64.encode(mac.finalize().into_bytes()),
+            key: self.key.clone(),
+            passphrase: self.passphrase.clone(),
+            timestamp,
+        }
+    }
+}
+
+fn subscription(channel: &str, symbols: &[String], auth: Option<CoinbaseAuth>) -> String {
+    let subscription = CoinbaseSubscriptionMessage {
+        r#type: String::from("subscribe"),
+        channels: vec![channel.to_string()],
+        product_ids: symbols.to_vec(),
+        auth,
+    };
+    serde_json::to_string::<CoinbaseSubscriptionMessage>(&subscription).unwrap()
+}
+
+const COINBASE_WS_API: &str = "wss://ws-feed.exchange.coinbase.com";
+
+const DEFAULT_SYMBOL: &str = "BTC-USD";
+
+/// `ticker` channel best bid/ask, last price and 24h volume.
+pub struct CoinbaseQuote {
+    symbols: Vec<String>,
+    sequences: Sequences,
+}
+
+impl CoinbaseQuote {
+    pub fn new(symbols: &[String]) -> Self {
+        CoinbaseQuote { symbols: symbols_or(symbols, DEFAULT_SYMBOL), sequences: Sequences::default() }
+    }
+}
+
+impl ExchangeAdapter for CoinbaseQuote {
+    fn exchange(&self) -> &str {
+        Venue::Coinbase.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        COINBASE_WS_API.to_string()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        vec![subscription("ticker", &self.symbols, None)]
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let quote = match response(serde_json::from_str(message)?) {
+            Some(CoinbaseMessage::Ticker(quote)) => quote,
+            _ => return Ok(Vec::new()),
+        };
+        if !self.sequences.in_order(&quote.product_id, quote.sequence) {
+            return Ok(Vec::new());
+        }
+        let symbol = canonical_symbol(&quote.product_id);
+        let exchange_ts = parse_millis(&quote.time);
+        Ok(vec![
+            MarketEvent::Quote(Quote {
+                venue: Venue::Coinbase,
+                symbol: symbol.clone(),
+                exchange_ts,
+                local_ts,
+                bid_price: quote.best_bid,
+                bid_size: quote.best_bid_size,
+                ask_price: quote.best_ask,
+                ask_size: quote.best_ask_size,
+            }),
+            MarketEvent::Ticker(Ticker {
+                venue: Venue::Coinbase,
+                symbol,
+                exchange_ts,
+                local_ts,
+                last_price: quote.price,
+                volume_24h: Some(quote.volume_24h),
+            }),
+        ])
+    }
+}
+
+/// Local book from `level2_batch`, or the unbatched `level2` when API
+/// credentials are set since Coinbase only serves it authenticated.
+///
+/// Every (re)subscribe starts with a `snapshot` that replaces the book, so a
+/// reconnect resyncs it completely.
+pub struct CoinbaseBook {
+    symbols: Vec<String>,
+    credentials: Option<Credentials>,
+    books: HashMap<String, OrderBook>,
+}
+
+impl CoinbaseBook {
+    pub fn new(symbols: &[String]) -> Self {
+        let credentials = Credentials::from_env();
+        info!(authenticated = credentials.is_some(), "Coinbase level2");
+        CoinbaseBook {
+            symbols: symbols_or(symbols, DEFAULT_SYMBOL),
+            credentials,
+            books: HashMap::new(),
+        }
+    }
+
+    fn snapshot(&mut self, product_id: String, bids: Vec<BookLevel>, asks: Vec<BookLevel>, local_ts: i64) -> Vec<MarketEvent> {
+        let book = self.books.entry(product_id.clone()).or_default();
+        book.snapshot_events(BookSnapshot {
+            venue: Venue::Coinbase,
+            symbol: canonical_symbol(&product_id),
+            exchange_ts: None,
+            local_ts,
+            bids,
+            asks,
+        })
+    }
+
+    fn update(&mut self, product_id: String, time: String, changes: Vec<(String, String, String)>, local_ts: i64) -> Vec<MarketEvent> {
+        let Some(book) = self.books.get_mut(&product_id) else {
+            debug!(product_id = %product_id, "Update before snapshot");
+            return Vec::new();
+        };
+        let symbol = canonical_symbol(&product_id);
+        let exchange_ts = parse_millis(&time);
+        changes
+            .into_iter()
+            .filter_map(|(side, price, size)| {
+                let Some(side) = Side::parse(&side) else {
+                    warn!(side = %side, "Unknown side");
+                    return None;
+                };
+                let (Ok(price), Ok(size)) = (price.parse::<f64>(), size.parse::<f64>()) else {
+                    warn!(price = %price, size = %size, "Invalid level");
+                    return None;
+                };
+                book.update(side, price, size);
+                Some(MarketEvent::BookDelta(BookDelta {
+                    venue: Venue::Coinbase,
+                    symbol: symbol.clone(),
+                    exchange_ts,
+                    local_ts,
+                    side,
+                    price,
+                    size,
+                }))
+            })
+            .collect()
+    }
+}
+
+impl ExchangeAdapter for CoinbaseBook {
+    fn exchange(&self) -> &str {
+        Venue::Coinbase.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        COINBASE_WS_API.to_string()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        match self.credentials {
+            Some(_) => Vec::new(),
+            None => vec![subscription("level2_batch", &self.symbols, None)],
+        }
+    }
+
+    fn subscriber(&self) -> Option<Subscriber> {
+        let credentials = self.credentials.clone()?;
+        let symbols = self.symbols.clone();
+        Some(Box::new(move || vec![subscription("level2", &symbols, Some(credentials.sign()))]))
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let events = match response(serde_json::from_str(message)?) {
+            Some(CoinbaseMessage::Snapshot { product_id, bids, asks }) => self.snapshot(product_id, bids, asks, local_ts),
+            Some(CoinbaseMessage::L2update { product_id, time, changes }) => self.update(product_id, time, changes, local_ts),
+            _ => Vec::new(),
+        };
+        Ok(events)
+    }
+}
+
+/// Last trade id and time seen for a product.
+struct LastMatch {
+    trade_id: u64,
+    ts: i64,
+}
+
+/// Trade prints from `matches`. Trade ids are consecutive per product, so a
+/// jump means matches were missed and is recorded as a gap between the two
+/// trades' times.
+pub struct CoinbaseTrade {
+    symbols: Vec<String>,
+    sequences: Sequences,
+    last: HashMap<String, LastMatch>,
+}
+
+impl CoinbaseTrade {
+    pub fn new(symbols: &[String]) -> Self {
+        CoinbaseTrade {
+            symbols: symbols_or(symbols, DEFAULT_SYMBOL),
+            sequences: Sequences::default(),
+            last: HashMap::new(),
+        }
+    }
+
+    fn trade(&mut self, trade: CoinbaseMessageMatch, local_ts: i64) -> Vec<MarketEvent> {
+        if !self.sequences.in_order(&trade.product_id, trade.sequence) {
+            return Vec::new();
+        }
+        // The maker's side is on the message, the aggressor took the other one.
+        let side = match Side::parse(&trade.side) {
+            Some(Side::Buy) => Side::Sell,
+            Some(Side::Sell) => Side::Buy,
+            None => {
+                warn!(side = %trade.side, "Unknown side");
+                return Vec::new();
+            }
+        };
+        let symbol = canonical_symbol(&trade.product_id);
+        let exchange_ts = parse_millis(&trade.time);
+        let ts = exchange_ts.unwrap_or(local_ts);
+        let mut events = Vec::with_capacity(2);
+        let last = self.last.insert(trade.product_id.clone(), LastMatch { trade_id: trade.trade_id, ts });
+        if let Some(last) = last.filter(|last| trade.trade_id > last.trade_id + 1) {
+            warn!(product_id = %trade.product_id, missed = trade.trade_id - last.trade_id - 1, "Trade id gap");
+            events.push(MarketEvent::Gap(Gap {
+                venue: Venue::Coinbase,
+                symbol: symbol.clone(),
+                exchange_ts,
+                local_ts,
+                from_ts: last.ts,
+                to_ts: ts,
+            }));
+        }
+        events.push(MarketEvent::Trade(Trade {
+            venue: Venue::Coinbase,
+            symbol,
+            exchange_ts,
+            local_ts,
+            side,
+            price: trade.price,
+            size: trade.size,
+            trade_id: Some(trade.trade_id.to_string()),
+        }));
+        events
+    }
+}
+
+impl ExchangeAdapter for CoinbaseTrade {
+    fn exchange(&self) -> &str {
+        Venue::Coinbase.as_str()
+    }
+
+    fn endpoint(&self) -> String {
+        COINBASE_WS_API.to_string()
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        vec![subscription("matches", &self.symbols, None)]
+    }
+
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error> {
+        let events = match response(serde_json::from_str(message)?) {
+            Some(CoinbaseMessage::Match(trade)) | Some(CoinbaseMessage::LastMatch(trade)) => self.trade(trade, local_ts),
+            _ => Vec::new(),
+        };
+        Ok(events)
+    }
+}
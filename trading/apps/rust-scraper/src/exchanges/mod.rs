@@ -0,0 +1,69 @@
+use rust_scraper_core::adapter::ExchangeAdapter;
+use rust_scraper_core::config::FeedConfig;
+use std::fmt;
+
+mod binance;
+mod bitmex;
+mod bybit;
+mod coinbase;
+
+/// The configured symbols, or `default` when none were given.
+fn symbols_or(symbols: &[String], default: &str) -> Vec<String> {
+    if symbols.is_empty() {
+        vec![default.to_string()]
+    } else {
+        symbols.to_vec()
+    }
+}
+
+/// Why a configured feed can't be run.
+#[derive(Debug)]
+pub enum FeedError {
+    /// No adapter for the venue and channel.
+    Unknown(String),
+    /// The adapter doesn't accept the feed's settings.
+    Invalid { feed: String, reason: String },
+}
+
+impl fmt::Display for FeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedError::Unknown(feed) => write!(f, "Unknown feed: {}", feed),
+            FeedError::Invalid { feed, reason } => write!(f, "Invalid feed {}: {}", feed, reason),
+        }
+    }
+}
+
+impl std::error::Error for FeedError {}
+
+/// Builds the adapter for a configured feed.
+pub fn adapter(feed: &FeedConfig) -> Result<Box<dyn ExchangeAdapter>, FeedError> {
+    let invalid = |reason| FeedError::Invalid { feed: feed.name(), reason };
+    let symbols = &feed.symbols;
+    let adapter: Box<dyn ExchangeAdapter> = match (feed.venue.as_str(), feed.channel.as_str()) {
+        ("bitmex", "book") => Box::new(bitmex::BitmexBook::new(bitmex::BookTable::L2, symbols, &feed.bands_bps)),
+        ("bitmex", "book-25") => Box::new(bitmex::BitmexBook::new(bitmex::BookTable::L2_25, symbols, &feed.bands_bps)),
+        ("bitmex", "book-10") => Box::new(bitmex::BitmexBook::new(bitmex::BookTable::Top10, symbols, &feed.bands_bps)),
+        ("bitmex", "quote") => Box::new(bitmex::BitmexQuote::new(symbols)),
+        ("bitmex", "trade") => Box::new(bitmex::BitmexTrade::new(symbols)),
+        ("bitmex", "instrument") => Box::new(bitmex::BitmexInstrument::new(symbols)),
+        ("bitmex", "funding") => Box::new(bitmex::BitmexFunding::new(symbols)),
+        ("bitmex", "liquidation") => Box::new(bitmex::BitmexLiquidation::new(symbols)),
+        ("binance", "quote") => Box::new(binance::BinanceQuote::new(symbols)),
+        ("binance", "book") => Box::new(binance::BinanceBook::new(symbols)),
+        ("binance", "trade") => Box::new(binance::BinanceTrade::aggregated(symbols)),
+        ("binance", "raw-trade") => Box::new(binance::BinanceTrade::raw(symbols)),
+        ("binance", "combined") => Box::new(binance::BinanceCombined::new(binance::Market::Spot, &feed.channels, symbols).map_err(invalid)?),
+        ("binance", "futures") => Box::new(binance::BinanceCombined::new(binance::Market::UsdM, &feed.channels, symbols).map_err(invalid)?),
+        ("coinbase", "quote") => Box::new(coinbase::CoinbaseQuote::new(symbols)),
+        ("coinbase", "book") => Box::new(coinbase::CoinbaseBook::new(symbols)),
+        ("coinbase", "trade") => Box::new(coinbase::CoinbaseTrade::new(symbols)),
+        ("bybit", "quote") => Box::new(bybit::BybitQuote::new(symbols)),
+        ("bybit", "book") => Box::new(bybit::BybitBook::new(bybit::Category::Spot, symbols)),
+        ("bybit", "linear-book") => Box::new(bybit::BybitBook::new(bybit::Category::Linear, symbols)),
+        ("bybit", "trade") => Box::new(bybit::BybitTrade::new(bybit::Category::Spot, symbols)),
+        ("bybit", "linear-trade") => Box::new(bybit::BybitTrade::new(bybit::Category::Linear, symbols)),
+        _ => return Err(FeedError::Unknown(feed.name())),
+    };
+    Ok(adapter)
+}
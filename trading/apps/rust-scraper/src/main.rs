@@ -0,0 +1,58 @@
+use rust_scraper_core::adapter;
+use rust_scraper_core::config::Config;
+use rust_scraper_core::logging;
+use rust_scraper_core::metrics;
+use rust_scraper_core::store::RedisWriter;
+use std::process;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+use tracing::{error, info_span, Instrument};
+
+mod exchanges;
+
+#[tokio::main]
+async fn main() {
+    logging::init();
+    let config = Arc::new(Config::load().unwrap_or_else(|e| panic!("{}", e)));
+    if !config.metrics.listen.is_empty() {
+        metrics::serve(&config.metrics.listen).expect("Can't serve metrics.");
+    }
+    let mut feeds = JoinSet::new();
+    for feed in &config.feeds {
+        let adapter = exchanges::adapter(feed).unwrap_or_else(|e| panic!("{}", e));
+        let config = Arc::clone(&config);
+        let name = feed.name();
+        let watchdog = config.watchdog_for(feed);
+        let depth_snapshots = feed.depth_snapshots.clone();
+        // Everything logged by the feed carries its name and exchange.
+        let span = info_span!("feed", feed = %name, exchange = adapter.exchange());
+        feeds.spawn(
+            async move {
+                let writer = RedisWriter::connect(&config, adapter.exchange())
+                    .await
+                    .expect("Can't connect to redis.");
+                let error = adapter::run(
+                    &name,
+                    adapter,
+                    writer,
+                    &config.reconnect,
+                    &watchdog,
+                    &config.pipeline,
+                    depth_snapshots.as_ref(),
+                )
+                .await;
+                (name, error)
+            }
+            .instrument(span),
+        );
+    }
+
+    // Feeds only stop once they've given up reconnecting; exit so the
+    // container gets restarted rather than silently running without them.
+    match feeds.join_next().await {
+        Some(Ok((name, error))) => error!(feed = %name, error = %error, "Feed stopped"),
+        Some(Err(e)) => error!(error = %e, "Feed failed"),
+        None => {}
+    }
+    process::exit(1);
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time;
+use tracing::{error, Instrument};
+
+use crate::config::{DepthSnapshotConfig, Overflow, PipelineConfig, ReconnectConfig, WatchdogConfig};
+use crate::depth::DepthSampler;
+use crate::feed::{Feed, FeedCommand, FeedEvent};
+use crate::fills::Fills;
+use crate::logging::RateLimit;
+use crate::metrics;
+use crate::model::{Gap, MarketEvent};
+use crate::queue::{queue, QueueReceiver, QueueSender};
+use crate::store::RedisWriter;
+use crate::watchdog::LastData;
+
+/// How a feed keeps its connection alive.
+#[derive(Debug, Clone)]
+pub enum Heartbeat {
+    /// Send a WebSocket Ping after this long without market data.
+    Ping(Duration),
+    /// Send an application level text frame at a fixed interval.
+    Text { interval: Duration, message: String },
+}
+
+/// Builds subscription messages afresh for every (re)connect, for venues that
+/// want them signed with the current time.
+pub type Subscriber = Box<dyn Fn() -> Vec<String> + Send + Sync>;
+
+/// When to replace a connection before the venue drops it, e.g. Binance
+/// closes connections after 24 hours.
+#[derive(Debug, Clone)]
+pub struct Rotation {
+    /// Age at which a replacement connection is opened.
+    pub after: Duration,
+    /// How long both connections are read before the old one is closed.
+    /// Adapters have to drop what arrives twice in that window.
+    pub overlap: Duration,
+}
+
+/// Everything the runner needs to know about one exchange feed.
+pub trait ExchangeAdapter: Send {
+    /// Exchange label, e.g. `BITMEX`, used for the `EXCHANGE` series label.
+    fn exchange(&self) -> &str;
+
+    fn endpoint(&self) -> String;
+
+    /// Messages to send after every (re)connect.
+    fn subscriptions(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Sent after `subscriptions`, built again on every (re)connect.
+    fn subscriber(&self) -> Option<Subscriber> {
+        None
+    }
+
+    /// Turns a text frame received at `local_ts` into normalized events.
+    fn parse(&mut self, message: &str, local_ts: i64) -> Result<Vec<MarketEvent>, serde_json::Error>;
+
+    fn heartbeat(&self) -> Heartbeat {
+        Heartbeat::Ping(Duration::from_secs(5))
+    }
+
+    fn rotation(&self) -> Option<Rotation> {
+        None
+    }
+
+    /// Requests for the socket, e.g. to resubscribe after a sequence gap.
+    /// Collected after every `parse`.
+    fn commands(&mut self) -> Vec<FeedCommand> {
+        Vec::new()
+    }
+}
+
+/// Remembers when each symbol last had data so the hole left by a reconnect
+/// or a dropped write batch can be recorded once data for it resumes.
+#[derive(Default)]
+struct Gaps {
+    last_seen: HashMap<String, i64>,
+    missed: HashMap<String, i64>,
+}
+
+impl Gaps {
+    fn missed(&mut self) {
+        self.missed.extend(self.last_seen.drain());
+    }
+
+    fn seen(&mut self, event: &MarketEvent) -> Option<MarketEvent> {
+        let to_ts = event.local_ts();
+        self.last_seen.insert(event.symbol().to_string(), to_ts);
+        let from_ts = self.missed.remove(event.symbol())?;
+        Some(MarketEvent::Gap(Gap {
+            venue: event.venue(),
+            symbol: event.symbol().to_string(),
+            exchange_ts: None,
+            local_ts: to_ts,
+            from_ts,
+            to_ts,
+        }))
+    }
+}
+
+/// Parses frames from the socket reader and queues the resulting events for
+/// the writer, one batch per frame. Commands from the adapter go back to the
+/// reader, and frames that produced events are reported to `last_data` for
+/// the reader's watchdog. Fills sharing a millisecond are merged and
+/// repeated ones dropped, see `fills`. Books are sampled here rather than by
+/// the writer so a dropped batch doesn't leave the sampled book wrong.
+async fn parse(
+    name: String,
+    mut adapter: Box<dyn ExchangeAdapter>,
+    mut frames: QueueReceiver<FeedEvent>,
+    writes: QueueSender<Vec<MarketEvent>>,
+    commands: mpsc::UnboundedSender<FeedCommand>,
+    last_data: LastData,
+    mut depth: Option<DepthSampler>,
+) {
+    let mut gaps = Gaps::default();
+    let mut fills = Fills::default();
+    let mut parse_errors = RateLimit::new(Duration::from_secs(10));
+    while let Some(frame) = frames.recv().await {
+        let parsed = match frame {
+            FeedEvent::Message { text, local_ts } => {
+                metrics::message_received(&name);
+                let parsed = adapter.parse(&text, local_ts);
+                for command in adapter.commands() {
+                    let _ = commands.send(command);
+                }
+                match parsed {
+                    Ok(parsed) => parsed,
+                    Err(e) => {
+                        metrics::parse_failed(&name);
+                        if let Some(suppressed) = parse_errors.check("parse") {
+                            error!(error = %e, message = %text, suppressed, "Parsing Failed");
+                        }
+                        continue;
+                    }
+                }
+            }
+            FeedEvent::Reconnected => {
+                metrics::reconnected(&name);
+                gaps.missed();
+                continue;
+            }
+        };
+        if !parsed.is_empty() {
+            last_data.data();
+        }
+        let mut events = Vec::with_capacity(parsed.len());
+        for mut event in parsed {
+            if !fills.merge(&mut event) {
+                continue;
+            }
+            metrics::event_parsed(&name, &event);
+            events.extend(gaps.seen(&event));
+            events.push(event);
+        }
+        if let Some(depth) = &mut depth {
+            let samples = depth.sample(&events);
+            events.extend(samples);
+        }
+        if events.is_empty() {
+            continue;
+        }
+        // A dropped batch leaves a hole for every symbol until it shows up again.
+        match writes.send(events).await {
+            Ok(true) => {}
+            Ok(false) => gaps.missed(),
+            Err(_) => return,
+        }
+    }
+}
+
+/// Writes batches to Redis, flushing whatever the batch window holds back
+/// once it expires even if nothing else arrives.
+async fn write(mut writer: RedisWriter, mut batches: QueueReceiver<Vec<MarketEvent>>) {
+    loop {
+        let batch = match writer.flush_deadline() {
+            Some(deadline) => match time::timeout_at(deadline.into(), batches.recv()).await {
+                Ok(batch) => batch,
+                Err(_) => {
+                    writer.flush().await;
+                    continue;
+                }
+            },
+            None => batches.recv().await,
+        };
+        match batch {
+            Some(events) => writer.write(&events).await,
+            None => break,
+        }
+    }
+    writer.flush().await;
+}
+
+/// Runs `adapter` as feed `name`, writing everything it parses through
+/// `writer`, until the feed gives up reconnecting. With `depth_snapshots` its
+/// books are also written as periodic depth snapshots.
+///
+/// The socket reader, parser and writer run as separate tasks joined by
+/// bounded queues, so a slow Redis doesn't hold up reading the socket for
+/// longer than the queues take to fill.
+pub async fn run(
+    name: &str,
+    adapter: Box<dyn ExchangeAdapter>,
+    writer: RedisWriter,
+    reconnect: &ReconnectConfig,
+    watchdog: &WatchdogConfig,
+    pipeline: &PipelineConfig,
+    depth_snapshots: Option<&DepthSnapshotConfig>,
+) -> tokio_tungstenite::tungstenite::Error {
+    let last_data = LastData::new();
+    let mut feed = Feed::new(&adapter.endpoint())
+        .heartbeat(adapter.heartbeat())
+        .reconnect(reconnect.clone())
+        .watchdog(watchdog.clone())
+        .last_data(last_data.clone())
+        .rotation(adapter.rotation());
+    for subscription in adapter.subscriptions() {
+        feed = feed.subscription(subscription);
+    }
+    if let Some(subscriber) = adapter.subscriber() {
+        feed = feed.subscriber(subscriber);
+    }
+    // Adapters that keep a book need every frame, so only writes are ever
+    // dropped; parsing keeps up with the socket unless the writer holds it up.
+    let parse_pipeline = PipelineConfig { overflow: Overflow::Block, ..pipeline.clone() };
+    let (frames, frames_rx) = queue(name, "parse", &parse_pipeline);
+    let (writes, writes_rx) = queue(name, "write", pipeline);
+    let (commands, commands_rx) = mpsc::unbounded_channel();
+    let depth = depth_snapshots.map(DepthSampler::new);
+    let parser = tokio::spawn(parse(name.to_string(), adapter, frames_rx, writes, commands, last_data, depth).in_current_span());
+    let writer = tokio::spawn(write(writer, writes_rx).in_current_span());
+
+    let error = feed.run(frames, commands_rx).await;
+    // The reader dropped its queue, let the other stages drain theirs.
+    let _ = parser.await;
+    let _ = writer.await;
+    error
+}
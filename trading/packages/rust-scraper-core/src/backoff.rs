@@ -0,0 +1,45 @@
+use rand::Rng;
+use std::time::Duration;
+
+use crate::config::ReconnectConfig;
+
+/// Jittered exponential backoff between reconnect attempts.
+///
+/// Each delay is drawn from the upper half of `initial * 2^attempt`, capped at
+/// the configured maximum, so a fleet of feeds dropped at once doesn't retry in
+/// lockstep.
+pub struct Backoff {
+    config: ReconnectConfig,
+    attempts: u32,
+}
+
+impl Backoff {
+    pub fn new(config: &ReconnectConfig) -> Self {
+        Backoff { config: config.clone(), attempts: 0 }
+    }
+
+    /// Consecutive attempts since the last `reset`.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// The delay before the next attempt, or `None` once `max_attempts` is used up.
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self.config.max_attempts > 0 && self.attempts >= self.config.max_attempts {
+            return None;
+        }
+        let exponential = self
+            .config
+            .initial_delay_ms
+            .saturating_mul(1u64.checked_shl(self.attempts).unwrap_or(u64::MAX))
+            .min(self.config.max_delay_ms);
+        self.attempts += 1;
+        let jittered = rand::thread_rng().gen_range(exponential / 2..=exponential);
+        Some(Duration::from_millis(jittered))
+    }
+
+    /// Call once the connection is healthy again.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
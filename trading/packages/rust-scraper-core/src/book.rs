@@ -0,0 +1,227 @@
+//! In-memory order books for adapters that receive incremental updates.
+//!
+//! Snapshots are written as levels rather than by deleting the stored book
+//! first, so the book's series keep their history: `snapshot_events` turns
+//! the levels a snapshot no longer has into zero size deltas.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::model::{BookDelta, BookLevel, BookSnapshot, MarketEvent, Side};
+
+/// A price usable as a map key; venues never send NaN prices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Price levels for one symbol, sorted so the best bid and ask are cheap to find.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        OrderBook::default()
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<Price, f64> {
+        match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    /// Replaces the whole book.
+    pub fn replace(&mut self, bids: &[BookLevel], asks: &[BookLevel]) {
+        self.clear();
+        for level in bids {
+            self.update(Side::Buy, level.price, level.size);
+        }
+        for level in asks {
+            self.update(Side::Sell, level.price, level.size);
+        }
+    }
+
+    /// Sets the size at `price`, removing the level when the size is zero.
+    pub fn update(&mut self, side: Side, price: f64, size: f64) {
+        let levels = self.side_mut(side);
+        if size > 0.0 {
+            levels.insert(Price(price), size);
+        } else {
+            levels.remove(&Price(price));
+        }
+    }
+
+    /// Replaces the book with `snapshot` and returns what to write for it: a
+    /// zero size delta for every level the old book had that the snapshot
+    /// doesn't, then the snapshot with the levels best first.
+    pub fn snapshot_events(&mut self, snapshot: BookSnapshot) -> Vec<MarketEvent> {
+        let old = std::mem::take(self);
+        self.replace(&snapshot.bids, &snapshot.asks);
+        let removed_bids = old.bids.keys().filter(|price| !self.bids.contains_key(price)).map(|price| (Side::Buy, price.0));
+        let removed_asks = old.asks.keys().filter(|price| !self.asks.contains_key(price)).map(|price| (Side::Sell, price.0));
+        let mut events: Vec<MarketEvent> = removed_bids
+            .chain(removed_asks)
+            .map(|(side, price)| {
+                MarketEvent::BookDelta(BookDelta {
+                    venue: snapshot.venue,
+                    symbol: snapshot.symbol.clone(),
+                    exchange_ts: snapshot.exchange_ts,
+                    local_ts: snapshot.local_ts,
+                    side,
+                    price,
+                    size: 0.0,
+                })
+            })
+            .collect();
+        events.push(MarketEvent::BookSnapshot(BookSnapshot {
+            bids: self.bids(),
+            asks: self.asks(),
+            ..snapshot
+        }));
+        events
+    }
+
+    /// Replaces the book with `snapshot` and returns a delta for every level
+    /// that changed, zero size for those it no longer has. For venues that
+    /// send the whole top of the book in every message, where writing it all
+    /// each time would mostly repeat the same sizes.
+    pub fn diff_events(&mut self, snapshot: BookSnapshot) -> Vec<MarketEvent> {
+        let old = std::mem::take(self);
+        self.replace(&snapshot.bids, &snapshot.asks);
+        let mut changed = Vec::new();
+        for (side, old_levels, levels) in [(Side::Buy, &old.bids, &self.bids), (Side::Sell, &old.asks, &self.asks)] {
+            changed.extend(old_levels.keys().filter(|price| !levels.contains_key(price)).map(|price| (side, price.0, 0.0)));
+            changed.extend(
+                levels
+                    .iter()
+                    .filter(|(price, size)| old_levels.get(price) != Some(size))
+                    .map(|(price, size)| (side, price.0, *size)),
+            );
+        }
+        changed
+            .into_iter()
+            .map(|(side, price, size)| {
+                MarketEvent::BookDelta(BookDelta {
+                    venue: snapshot.venue,
+                    symbol: snapshot.symbol.clone(),
+                    exchange_ts: snapshot.exchange_ts,
+                    local_ts: snapshot.local_ts,
+                    side,
+                    price,
+                    size,
+                })
+            })
+            .collect()
+    }
+
+    pub fn best_bid(&self) -> Option<BookLevel> {
+        self.bids.iter().next_back().map(|(price, size)| BookLevel { price: price.0, size: *size })
+    }
+
+    pub fn best_ask(&self) -> Option<BookLevel> {
+        self.asks.iter().next().map(|(price, size)| BookLevel { price: price.0, size: *size })
+    }
+
+    /// Total bid and ask size priced within `bps` basis points of `mid`.
+    pub fn depth_within(&self, mid: f64, bps: u32) -> (f64, f64) {
+        let distance = mid * bps as f64 / 10_000.0;
+        let bid_size = self.bids.range(Price(mid - distance)..).map(|(_, size)| size).sum();
+        let ask_size = self.asks.range(..=Price(mid + distance)).map(|(_, size)| size).sum();
+        (bid_size, ask_size)
+    }
+
+    /// Bids, best first.
+    pub fn bids(&self) -> Vec<BookLevel> {
+        self.top_bids(usize::MAX)
+    }
+
+    /// Asks, best first.
+    pub fn asks(&self) -> Vec<BookLevel> {
+        self.top_asks(usize::MAX)
+    }
+
+    /// The best `depth` bids, best first.
+    pub fn top_bids(&self, depth: usize) -> Vec<BookLevel> {
+        self.bids.iter().rev().take(depth).map(|(price, size)| BookLevel { price: price.0, size: *size }).collect()
+    }
+
+    /// The best `depth` asks, best first.
+    pub fn top_asks(&self, depth: usize) -> Vec<BookLevel> {
+        self.asks.iter().take(depth).map(|(price, size)| BookLevel { price: price.0, size: *size }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Venue;
+
+    fn snapshot(bids: &[(f64, f64)], asks: &[(f64, f64)]) -> BookSnapshot {
+        let levels = |levels: &[(f64, f64)]| levels.iter().map(|&(price, size)| BookLevel { price, size }).collect();
+        BookSnapshot {
+            venue: Venue::Bybit,
+            symbol: "BTC-USDT".to_string(),
+            exchange_ts: Some(1000),
+            local_ts: 1003,
+            bids: levels(bids),
+            asks: levels(asks),
+        }
+    }
+
+    fn deltas(events: &[MarketEvent]) -> Vec<(Side, f64, f64)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                MarketEvent::BookDelta(delta) => Some((delta.side, delta.price, delta.size)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_writes_removed_levels_as_zero() {
+        let mut book = OrderBook::new();
+        book.snapshot_events(snapshot(&[(99.0, 1.0), (98.0, 2.0)], &[(101.0, 1.0)]));
+        let events = book.snapshot_events(snapshot(&[(99.0, 3.0)], &[(102.0, 1.0)]));
+        assert_eq!(deltas(&events), vec![(Side::Buy, 98.0, 0.0), (Side::Sell, 101.0, 0.0)]);
+        assert_eq!(events.last(), Some(&MarketEvent::BookSnapshot(snapshot(&[(99.0, 3.0)], &[(102.0, 1.0)]))));
+        assert_eq!(book.bids(), vec![BookLevel { price: 99.0, size: 3.0 }]);
+    }
+
+    #[test]
+    fn diff_writes_only_changed_levels() {
+        let mut book = OrderBook::new();
+        book.diff_events(snapshot(&[(99.0, 1.0), (98.0, 2.0)], &[(101.0, 1.0)]));
+        let events = book.diff_events(snapshot(&[(99.0, 1.0), (97.0, 2.0)], &[(101.0, 1.5)]));
+        assert_eq!(
+            deltas(&events),
+            vec![(Side::Buy, 98.0, 0.0), (Side::Buy, 97.0, 2.0), (Side::Sell, 101.0, 1.5)]
+        );
+        assert_eq!(events.len(), 3);
+    }
+}
@@ -0,0 +1,383 @@
+//! Scraper configuration, read from a TOML file and then overridden from the
+//! environment so a deployment can change symbols without a rebuild.
+//!
+//! ```toml
+//! retention = 86400000
+//! key_template = "{venue}:{pair}"
+//!
+//! [redis]
+//! host = "cache"
+//! batch_ms = 50
+//!
+//! [reconnect]
+//! max_attempts = 10
+//!
+//! [watchdog]
+//! stale_after_ms = 30000
+//!
+//! [metrics]
+//! listen = "0.0.0.0:9898"
+//!
+//! [pipeline]
+//! queue_size = 4096
+//! overflow = "drop"
+//!
+//! [[feeds]]
+//! venue = "binance"
+//! channel = "quote"
+//! symbols = ["BTCUSDT", "ETHUSDT"]
+//! # quiet markets can wait longer before being declared stale
+//! stale_after_ms = 120000
+//!
+//! [[feeds]]
+//! venue = "bitmex"
+//! channel = "book"
+//! # top 25 levels a side as one record every second
+//! depth_snapshots = { interval_ms = 1000, depth = 25 }
+//! # top of book plus the size within 10, 25 and 50 bps of the mid
+//! bands_bps = [10, 25, 50]
+//! ```
+//!
+//! Environment overrides:
+//! - `SCRAPER_CONFIG`: path of the file, defaults to `scraper.toml`
+//! - `REDIS_HOST`, `REDIS_PORT`, `REDIS_PASSWORD`
+//! - `SCRAPER_RETENTION`, `SCRAPER_KEY_TEMPLATE`
+//! - `SCRAPER_STALE_AFTER_MS`: default stale threshold for feeds without their own
+//! - `SCRAPER_METRICS_LISTEN`: address for the `/metrics` endpoint, empty to disable
+//! - `SCRAPER_FEEDS`: comma separated `venue-channel` names to run, e.g. `bitmex-quote,bybit-quote`
+//! - `SCRAPER_{VENUE}_{CHANNEL}_SYMBOLS`: symbols for one feed, comma or whitespace
+//!   separated, e.g. `SCRAPER_BINANCE_QUOTE_SYMBOLS="BTCUSDT ETHUSDT"`
+//! - `SCRAPER_{VENUE}_{CHANNEL}_CHANNELS`: channels of a multiplexing feed, e.g.
+//!   `SCRAPER_BINANCE_COMBINED_CHANNELS="quote trade"`
+
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+
+use tracing::info;
+
+pub const DEFAULT_CONFIG_PATH: &str = "scraper.toml";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RedisConfig {
+    pub host: String,
+    pub port: u16,
+    pub password: Option<String>,
+    /// How long samples may wait to be sent in one pipeline. 0 sends each
+    /// message's samples as soon as it's parsed.
+    pub batch_ms: u64,
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        RedisConfig {
+            host: "cache".to_string(),
+            port: 6379,
+            password: None,
+            batch_ms: 0,
+        }
+    }
+}
+
+/// Backoff between reconnect attempts. `max_attempts` counts consecutive
+/// failures, 0 retries forever. A connect, including sending the
+/// subscriptions, that takes longer than `connect_timeout_ms` counts as
+/// failed, 0 waits for as long as it takes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ReconnectConfig {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub max_attempts: u32,
+    pub connect_timeout_ms: u64,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        ReconnectConfig {
+            initial_delay_ms: 500,
+            max_delay_ms: 30000,
+            max_attempts: 0,
+            connect_timeout_ms: 10000,
+        }
+    }
+}
+
+/// When a connection counts as dead. `stale_after_ms` is how long a feed may
+/// go without a frame that parses to market data (acks and application level
+/// pongs don't count), `pong_timeout_ms` how long a Ping may go
+/// unanswered. 0 disables either check.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    pub stale_after_ms: u64,
+    pub pong_timeout_ms: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        WatchdogConfig {
+            stale_after_ms: 30000,
+            pong_timeout_ms: 10000,
+        }
+    }
+}
+
+/// Where to serve Prometheus metrics. An empty `listen` disables the endpoint.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub listen: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            listen: "0.0.0.0:9898".to_string(),
+        }
+    }
+}
+
+/// What a feed does when the queue to its Redis writer is full. Frames are
+/// never dropped before parsing, adapters that keep a local book would be
+/// left with a wrong one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overflow {
+    /// Wait for room. Nothing is lost, but a stalled Redis eventually stops
+    /// the socket from being read.
+    #[default]
+    Block,
+    /// Discard the newest batch of events and record a gap, keeping the
+    /// socket drained.
+    Drop,
+}
+
+/// Queues between each feed's socket reader, parser and Redis writer.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PipelineConfig {
+    pub queue_size: usize,
+    pub overflow: Overflow,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        PipelineConfig {
+            queue_size: 4096,
+            overflow: Overflow::Block,
+        }
+    }
+}
+
+/// How often a book feed records its top levels as one `BOOK:SNAPSHOT`
+/// stream entry, and how many levels a side.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DepthSnapshotConfig {
+    pub interval_ms: u64,
+    pub depth: usize,
+}
+
+impl Default for DepthSnapshotConfig {
+    fn default() -> Self {
+        DepthSnapshotConfig {
+            interval_ms: 1000,
+            depth: 25,
+        }
+    }
+}
+
+/// One adapter to run: a venue, one of its channels and the venue native
+/// symbols to subscribe to. An empty symbol list uses the adapter's default.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedConfig {
+    pub venue: String,
+    pub channel: String,
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    /// Overrides `watchdog.stale_after_ms` for this feed.
+    #[serde(default)]
+    pub stale_after_ms: Option<u64>,
+    /// Channels carried together by a multiplexing feed such as
+    /// `binance-combined`.
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Depth bands in basis points of the mid, for book feeds that derive
+    /// their top of book.
+    #[serde(default)]
+    pub bands_bps: Vec<u32>,
+    /// Records the feed's books periodically, for book feeds only.
+    #[serde(default)]
+    pub depth_snapshots: Option<DepthSnapshotConfig>,
+}
+
+impl FeedConfig {
+    pub fn new(name: &str) -> Option<Self> {
+        let (venue, channel) = name.split_once('-')?;
+        Some(FeedConfig {
+            venue: venue.to_string(),
+            channel: channel.to_string(),
+            symbols: Vec::new(),
+            stale_after_ms: None,
+            channels: Vec::new(),
+            bands_bps: Vec::new(),
+            depth_snapshots: None,
+        })
+    }
+
+    /// `venue-channel`, as used in `SCRAPER_FEEDS`.
+    pub fn name(&self) -> String {
+        format!("{}-{}", self.venue, self.channel)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub redis: RedisConfig,
+    pub reconnect: ReconnectConfig,
+    pub watchdog: WatchdogConfig,
+    pub metrics: MetricsConfig,
+    pub pipeline: PipelineConfig,
+    /// Series retention in millis.
+    pub retention: u64,
+    /// Key prefix for every series; `{venue}`, `{pair}` and `{symbol}` are substituted.
+    pub key_template: String,
+    pub feeds: Vec<FeedConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            redis: RedisConfig::default(),
+            reconnect: ReconnectConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            metrics: MetricsConfig::default(),
+            pipeline: PipelineConfig::default(),
+            retention: 86400000,
+            key_template: "{venue}:{pair}".to_string(),
+            feeds: ["bitmex-quote", "binance-quote", "coinbase-quote", "bybit-quote"]
+                .iter()
+                .filter_map(|name| FeedConfig::new(name))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Env { name: String, value: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "Can't read config: {}", e),
+            ConfigError::Toml(e) => write!(f, "Can't parse config: {}", e),
+            ConfigError::Env { name, value } => write!(f, "Invalid value for {}: {:?}", name, value),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn env_list(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> Result<Option<T>, ConfigError> {
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| ConfigError::Env { name: name.to_string(), value }),
+        Err(_) => Ok(None),
+    }
+}
+
+impl Config {
+    /// Reads `$SCRAPER_CONFIG` (or `scraper.toml`), falling back to the
+    /// defaults when it doesn't exist, then applies environment overrides.
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = env::var("SCRAPER_CONFIG").unwrap_or(DEFAULT_CONFIG_PATH.to_string());
+        let mut config = match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).map_err(ConfigError::Toml)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                info!(path, "No config, using defaults");
+                Config::default()
+            }
+            Err(e) => return Err(ConfigError::Io(e)),
+        };
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// The watchdog thresholds for `feed`, with its own overrides applied.
+    pub fn watchdog_for(&self, feed: &FeedConfig) -> WatchdogConfig {
+        WatchdogConfig {
+            stale_after_ms: feed.stale_after_ms.unwrap_or(self.watchdog.stale_after_ms),
+            ..self.watchdog.clone()
+        }
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Ok(host) = env::var("REDIS_HOST") {
+            self.redis.host = host;
+        }
+        if let Some(port) = env_parse("REDIS_PORT")? {
+            self.redis.port = port;
+        }
+        if let Ok(password) = env::var("REDIS_PASSWORD") {
+            self.redis.password = Some(password);
+        }
+        if let Some(retention) = env_parse("SCRAPER_RETENTION")? {
+            self.retention = retention;
+        }
+        if let Ok(key_template) = env::var("SCRAPER_KEY_TEMPLATE") {
+            self.key_template = key_template;
+        }
+        if let Some(stale_after_ms) = env_parse("SCRAPER_STALE_AFTER_MS")? {
+            self.watchdog.stale_after_ms = stale_after_ms;
+        }
+        if let Ok(listen) = env::var("SCRAPER_METRICS_LISTEN") {
+            self.metrics.listen = listen;
+        }
+        if let Ok(feeds) = env::var("SCRAPER_FEEDS") {
+            let mut selected = Vec::new();
+            for name in env_list(&feeds) {
+                let feed = match self.feeds.iter().find(|feed| feed.name() == name) {
+                    Some(feed) => feed.clone(),
+                    None => FeedConfig::new(&name).ok_or(ConfigError::Env {
+                        name: "SCRAPER_FEEDS".to_string(),
+                        value: feeds.clone(),
+                    })?,
+                };
+                selected.push(feed);
+            }
+            self.feeds = selected;
+        }
+        for feed in &mut self.feeds {
+            let prefix = format!("SCRAPER_{}_{}", feed.venue, feed.channel)
+                .to_uppercase()
+                .replace('-', "_");
+            if let Ok(symbols) = env::var(format!("{}_SYMBOLS", prefix)) {
+                feed.symbols = env_list(&symbols);
+            }
+            if let Ok(channels) = env::var(format!("{}_CHANNELS", prefix)) {
+                feed.channels = env_list(&channels);
+            }
+        }
+        Ok(())
+    }
+}
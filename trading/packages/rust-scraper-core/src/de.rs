@@ -0,0 +1,42 @@
+use serde::de;
+use serde::{Deserialize, Deserializer};
+
+use crate::model::BookLevel;
+
+/// Deserializes a float that the exchange sends as a JSON string, e.g. `"43244.39000000"`.
+pub fn de_float_from_str<'a, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let str_val = String::deserialize(deserializer)?;
+    str_val.parse::<f64>().map_err(de::Error::custom)
+}
+
+/// Like `de_float_from_str` for fields a venue may omit, e.g. in a delta, or
+/// send empty, e.g. the funding rate of a dated future.
+pub fn de_opt_float_from_str<'a, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(str_val) if str_val.is_empty() => Ok(None),
+        Some(str_val) => str_val.parse::<f64>().map(Some).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Deserializes `[["price", "size"], ...]` price levels.
+pub fn de_levels_from_str<'a, D>(deserializer: D) -> Result<Vec<BookLevel>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    Vec::<(String, String)>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(price, size)| {
+            Ok(BookLevel {
+                price: price.parse::<f64>().map_err(de::Error::custom)?,
+                size: size.parse::<f64>().map_err(de::Error::custom)?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,79 @@
+//! Samples the books a feed writes into `DepthSnapshot`s at a fixed interval,
+//! so the book at a point in time can be read back from a single record
+//! instead of scanning every `BOOK:{SIDE}:{PRICE}` series.
+
+use std::collections::HashMap;
+
+use crate::book::OrderBook;
+use crate::config::DepthSnapshotConfig;
+use crate::model::{DepthSnapshot, MarketEvent, Venue};
+
+/// One symbol's book, rebuilt from the feed's book events.
+#[derive(Default)]
+struct SampledBook {
+    book: OrderBook,
+    seq: u64,
+    /// Time of the next interval boundary; the first change at or after it
+    /// is sampled.
+    next_ts: i64,
+}
+
+pub struct DepthSampler {
+    interval_ms: i64,
+    depth: usize,
+    books: HashMap<(Venue, String), SampledBook>,
+}
+
+impl DepthSampler {
+    pub fn new(config: &DepthSnapshotConfig) -> Self {
+        DepthSampler {
+            interval_ms: config.interval_ms.max(1) as i64,
+            depth: config.depth,
+            books: HashMap::new(),
+        }
+    }
+
+    /// Applies the events parsed from one frame and samples the books they
+    /// changed that are due. Frames are applied whole so a sample never sees
+    /// half of a multi-level update.
+    pub fn sample(&mut self, events: &[MarketEvent]) -> Vec<MarketEvent> {
+        let mut changed: Vec<&MarketEvent> = Vec::new();
+        for event in events {
+            let key = (event.venue(), event.symbol().to_string());
+            match event {
+                MarketEvent::BookSnapshot(snapshot) => {
+                    self.books.entry(key).or_default().book.replace(&snapshot.bids, &snapshot.asks);
+                }
+                MarketEvent::BookDelta(delta) => {
+                    self.books.entry(key).or_default().book.update(delta.side, delta.price, delta.size);
+                }
+                _ => continue,
+            }
+            // Keep the last event per book, it stamps the sample.
+            changed.retain(|last| last.venue() != event.venue() || last.symbol() != event.symbol());
+            changed.push(event);
+        }
+        let mut samples = Vec::new();
+        for event in changed {
+            let Some(sampled) = self.books.get_mut(&(event.venue(), event.symbol().to_string())) else {
+                continue;
+            };
+            let ts = event.ts();
+            if ts < sampled.next_ts {
+                continue;
+            }
+            sampled.seq += 1;
+            sampled.next_ts = (ts / self.interval_ms + 1) * self.interval_ms;
+            samples.push(MarketEvent::DepthSnapshot(DepthSnapshot {
+                venue: event.venue(),
+                symbol: event.symbol().to_string(),
+                exchange_ts: event.exchange_ts(),
+                local_ts: event.local_ts(),
+                seq: sampled.seq,
+                bids: sampled.book.top_bids(self.depth),
+                asks: sampled.book.top_asks(self.depth),
+            }));
+        }
+        samples
+    }
+}
@@ -0,0 +1,381 @@
+use futures_util::future::BoxFuture;
+use futures_util::{SinkExt, StreamExt};
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{self, timeout};
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, error, info, warn};
+
+use crate::adapter::{Heartbeat, Rotation, Subscriber};
+use crate::backoff::Backoff;
+use crate::config::{ReconnectConfig, WatchdogConfig};
+use crate::queue::{Closed, QueueSender};
+use crate::time::now_millis;
+use crate::watchdog::{LastData, Watchdog};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How often a read waits before checking the watchdog and heartbeat.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What the feed hands to the next stage.
+#[derive(Debug, Clone)]
+pub enum FeedEvent {
+    /// A text frame and when it was received.
+    Message { text: String, local_ts: i64 },
+    /// The connection was re-established after being lost; anything sent in
+    /// between was missed.
+    Reconnected,
+}
+
+/// What a later stage can ask of the socket, e.g. to resubscribe after
+/// noticing a sequence gap.
+#[derive(Debug, Clone)]
+pub enum FeedCommand {
+    /// Send a text frame.
+    Send(String),
+    /// Drop the connection and reconnect, replaying the subscriptions.
+    Reconnect(String),
+}
+
+fn closed() -> tungstenite::Error {
+    tungstenite::Error::Io(io::Error::new(io::ErrorKind::BrokenPipe, "Feed consumer stopped"))
+}
+
+/// The connection replacing the current one and when it was opened.
+struct Replacement {
+    socket: Socket,
+    opened: Instant,
+}
+
+/// A replacement connection being opened.
+type Opening<'a> = BoxFuture<'a, Result<Socket, Box<tungstenite::Error>>>;
+
+/// The replacement connection once it's open, if one is being opened.
+async fn replacement_opened(opening: &mut Option<Opening<'_>>) -> Result<Socket, Box<tungstenite::Error>> {
+    match opening {
+        Some(opening) => opening.await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Next frame of the replacement connection, if there is one.
+async fn next_replacement(replacement: &mut Option<Replacement>) -> Option<Result<Message, tungstenite::Error>> {
+    match replacement {
+        Some(replacement) => replacement.socket.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// A WebSocket market data feed: where to connect, what to subscribe to once
+/// connected, how to keep the connection alive, when to give up on a silent
+/// connection, how to retry when it drops and when to replace it before the
+/// venue drops it.
+pub struct Feed {
+    url: String,
+    subscriptions: Vec<String>,
+    subscriber: Option<Subscriber>,
+    heartbeat: Heartbeat,
+    reconnect: ReconnectConfig,
+    watchdog: WatchdogConfig,
+    last_data: LastData,
+    rotation: Option<Rotation>,
+}
+
+impl Feed {
+    pub fn new(url: &str) -> Self {
+        Feed {
+            url: url.to_string(),
+            subscriptions: Vec::new(),
+            subscriber: None,
+            heartbeat: Heartbeat::Ping(Duration::from_secs(5)),
+            reconnect: ReconnectConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            last_data: LastData::new(),
+            rotation: None,
+        }
+    }
+
+    /// Adds a message to send every time the socket (re)connects.
+    pub fn subscription(mut self, message: String) -> Self {
+        self.subscriptions.push(message);
+        self
+    }
+
+    /// Adds messages built anew every time the socket (re)connects, e.g. signed ones.
+    pub fn subscriber(mut self, subscriber: Subscriber) -> Self {
+        self.subscriber = Some(subscriber);
+        self
+    }
+
+    pub fn heartbeat(mut self, heartbeat: Heartbeat) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    pub fn reconnect(mut self, reconnect: ReconnectConfig) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn watchdog(mut self, watchdog: WatchdogConfig) -> Self {
+        self.watchdog = watchdog;
+        self
+    }
+
+    /// Where the stage consuming the frames reports the ones that carried
+    /// market data; only those keep the watchdog from declaring the feed stale.
+    pub fn last_data(mut self, last_data: LastData) -> Self {
+        self.last_data = last_data;
+        self
+    }
+
+    pub fn rotation(mut self, rotation: Option<Rotation>) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Connects and replays every subscription, failing with `TimedOut` if
+    /// that takes longer than the reconnect config's `connect_timeout_ms`.
+    async fn connect(&self) -> Result<Socket, Box<tungstenite::Error>> {
+        if self.reconnect.connect_timeout_ms == 0 {
+            return self.handshake().await;
+        }
+        let limit = Duration::from_millis(self.reconnect.connect_timeout_ms);
+        match timeout(limit, self.handshake()).await {
+            Ok(socket) => socket,
+            Err(_) => Err(Box::new(tungstenite::Error::Io(io::Error::new(io::ErrorKind::TimedOut, "Connect timed out")))),
+        }
+    }
+
+    async fn handshake(&self) -> Result<Socket, Box<tungstenite::Error>> {
+        let (mut socket, _) = connect_async(self.url.as_str()).await?;
+        info!(url = %self.url, "Connected");
+        for subscription in &self.subscriptions {
+            info!(subscription = %subscription, "Subscribing");
+            socket.send(Message::from(subscription.as_str())).await?;
+        }
+        // Not logged, these may carry credentials.
+        for subscription in self.subscriber.iter().flat_map(|subscriber| subscriber()) {
+            info!("Subscribing with signed request");
+            socket.send(Message::from(subscription)).await?;
+        }
+        Ok(socket)
+    }
+
+    /// Reads the feed into `queue`, reconnecting with backoff whenever the
+    /// connection fails or goes stale, and carrying out `commands`.
+    ///
+    /// Only returns once `max_attempts` consecutive reconnects have failed, or
+    /// the consumer of `queue` has gone, with the last error.
+    pub async fn run(&self, queue: QueueSender<FeedEvent>, mut commands: mpsc::UnboundedReceiver<FeedCommand>) -> tungstenite::Error {
+        let mut backoff = Backoff::new(&self.reconnect);
+        let mut reconnects: u64 = 0;
+        loop {
+            let error = match self.connect().await {
+                Ok(mut socket) => {
+                    if reconnects > 0 && queue.send(FeedEvent::Reconnected).await.is_err() {
+                        return closed();
+                    }
+                    let error = self.read(&mut socket, &queue, &mut commands, &mut backoff).await;
+                    // Best effort, the peer may well be gone already.
+                    let _ = socket.close(None).await;
+                    match error {
+                        Err(Closed) => return closed(),
+                        Ok(error) => {
+                            reconnects += 1;
+                            warn!(url = %self.url, error = %error, reconnects, "Connection lost");
+                            error
+                        }
+                    }
+                }
+                Err(error) => {
+                    warn!(url = %self.url, error = %error, "Can't connect");
+                    *error
+                }
+            };
+            match backoff.next_delay() {
+                Some(delay) => {
+                    info!(delay_ms = delay.as_millis() as u64, attempt = backoff.attempts(), "Reconnecting");
+                    time::sleep(delay).await;
+                }
+                None => {
+                    error!(url = %self.url, attempts = backoff.attempts(), "Giving up");
+                    return error;
+                }
+            }
+        }
+    }
+
+    /// Queues every text frame until the connection fails, the watchdog
+    /// declares it stale, a reconnect is requested or the queue's consumer
+    /// goes away.
+    ///
+    /// Pings are answered and the heartbeat is sent according to its policy.
+    /// The backoff is reset once a text frame has been received.
+    ///
+    /// With a rotation, a replacement connection is opened once this one is
+    /// old enough and both are read for the overlap before the old one is
+    /// closed. If the old one fails during the overlap the replacement takes
+    /// over straight away. The replacement is opened alongside reading, so the
+    /// current connection is still read, and its heartbeat and watchdog kept,
+    /// while the replacement connects.
+    async fn read(
+        &self,
+        socket: &mut Socket,
+        queue: &QueueSender<FeedEvent>,
+        commands: &mut mpsc::UnboundedReceiver<FeedCommand>,
+        backoff: &mut Backoff,
+    ) -> Result<tungstenite::Error, Closed> {
+        let mut watchdog = Watchdog::new(&self.watchdog, self.last_data.clone());
+        let mut last_ping = Instant::now();
+        let mut last_heartbeat = Instant::now();
+        let mut opened = Instant::now();
+        let mut replacement: Option<Replacement> = None;
+        let mut opening: Option<Opening> = None;
+        loop {
+            if let Some(rotation) = &self.rotation {
+                match &replacement {
+                    Some(next) if next.opened.elapsed() >= rotation.overlap => {
+                        info!(url = %self.url, "Rotated connection");
+                        let next = replacement.take().unwrap();
+                        let mut old = std::mem::replace(socket, next.socket);
+                        let _ = old.close(None).await;
+                        opened = next.opened;
+                    }
+                    None if opening.is_none() && opened.elapsed() >= rotation.after => {
+                        info!(url = %self.url, age_s = opened.elapsed().as_secs(), "Opening replacement connection");
+                        opening = Some(Box::pin(self.connect()));
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(reason) = watchdog.check() {
+                warn!(url = %self.url, reason = %reason, "Feed is stale");
+                return Ok(tungstenite::Error::Io(io::Error::new(io::ErrorKind::TimedOut, reason)));
+            }
+            if let Some(heartbeat) = self.due_heartbeat(&mut watchdog, &mut last_ping, &mut last_heartbeat) {
+                if let Err(e) = socket.send(heartbeat).await {
+                    return Ok(e);
+                }
+            }
+
+            let frame = tokio::select! {
+                frame = timeout(POLL_INTERVAL, socket.next()) => frame,
+                next = replacement_opened(&mut opening) => {
+                    opening = None;
+                    match next {
+                        Ok(socket) => replacement = Some(Replacement { socket, opened: Instant::now() }),
+                        Err(e) => {
+                            // Try again after another overlap; the current connection still works.
+                            warn!(url = %self.url, error = %e, "Can't open replacement connection");
+                            if let Some(rotation) = &self.rotation {
+                                opened += rotation.overlap;
+                            }
+                        }
+                    }
+                    continue;
+                }
+                frame = next_replacement(&mut replacement) => {
+                    let next = replacement.as_mut().unwrap();
+                    match frame {
+                        Some(Ok(Message::Text(text))) => {
+                            queue.send(FeedEvent::Message { text, local_ts: now_millis() }).await?;
+                        }
+                        Some(Ok(Message::Ping(_))) => {
+                            if let Err(e) = next.socket.send(Message::Pong("pong".as_bytes().to_vec())).await {
+                                warn!(url = %self.url, error = %e, "Replacement connection lost");
+                                replacement = None;
+                            }
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            warn!(url = %self.url, error = %e, "Replacement connection lost");
+                            replacement = None;
+                        }
+                        None => {
+                            warn!(url = %self.url, "Replacement connection closed");
+                            replacement = None;
+                        }
+                    }
+                    continue;
+                }
+                Some(command) = commands.recv() => {
+                    match command {
+                        FeedCommand::Send(message) => {
+                            info!(message = %message, "Sending");
+                            if let Err(e) = socket.send(Message::from(message)).await {
+                                return Ok(e);
+                            }
+                        }
+                        FeedCommand::Reconnect(reason) => {
+                            warn!(reason = %reason, "Reconnect requested");
+                            return Ok(tungstenite::Error::Io(io::Error::other(reason)));
+                        }
+                    }
+                    continue;
+                }
+            };
+            let text = match frame {
+                Err(_) => continue,
+                Ok(None) | Ok(Some(Err(_))) if replacement.is_some() => {
+                    warn!(url = %self.url, "Connection lost during rotation, switching to replacement");
+                    let next = replacement.take().unwrap();
+                    *socket = next.socket;
+                    opened = next.opened;
+                    continue;
+                }
+                Ok(None) => return Ok(tungstenite::Error::ConnectionClosed),
+                Ok(Some(Err(error))) => return Ok(error),
+                Ok(Some(Ok(Message::Text(text)))) => text,
+                Ok(Some(Ok(Message::Ping(_)))) => {
+                    debug!("Received Ping");
+                    if let Err(e) = socket.send(Message::Pong("pong".as_bytes().to_vec())).await {
+                        return Ok(e);
+                    }
+                    debug!("Sent Pong");
+                    continue;
+                }
+                Ok(Some(Ok(Message::Pong(_)))) => {
+                    debug!("Received Pong");
+                    watchdog.pong();
+                    continue;
+                }
+                Ok(Some(Ok(Message::Close(frame)))) => {
+                    info!(frame = ?frame, "Received Close");
+                    continue;
+                }
+                Ok(Some(Ok(message))) => {
+                    warn!(message = %message, "Unexpected frame");
+                    continue;
+                }
+            };
+            backoff.reset();
+            queue.send(FeedEvent::Message { text, local_ts: now_millis() }).await?;
+        }
+    }
+
+    /// The heartbeat to send now, if one is due.
+    fn due_heartbeat(&self, watchdog: &mut Watchdog, last_ping: &mut Instant, last_heartbeat: &mut Instant) -> Option<Message> {
+        match &self.heartbeat {
+            Heartbeat::Ping(interval)
+                if !watchdog.awaiting_pong()
+                    && watchdog.since_data() >= *interval
+                    && last_ping.elapsed() >= *interval =>
+            {
+                debug!("Sending Ping");
+                *last_ping = Instant::now();
+                watchdog.ping();
+                Some(Message::Ping("ping".as_bytes().to_vec()))
+            }
+            Heartbeat::Text { interval, message } if last_heartbeat.elapsed() >= *interval => {
+                *last_heartbeat = Instant::now();
+                Some(Message::from(message.as_str()))
+            }
+            _ => None,
+        }
+    }
+}
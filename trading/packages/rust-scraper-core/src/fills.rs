@@ -0,0 +1,127 @@
+//! Merges fills that would land on the same series sample.
+//!
+//! Trade and liquidation series hold one sample per millisecond and side,
+//! written with the last-wins duplicate policy, while a single message often
+//! carries several fills stamped with the same millisecond. Each fill is
+//! written as the running total of its millisecond instead, so whichever
+//! write lands last holds the whole volume, priced at its VWAP.
+//!
+//! Venues repeat recent trades after a reconnect, e.g. BitMEX's `partial` and
+//! Coinbase's `last_match`, so fills are only merged once per trade id, and
+//! fills older than the milliseconds still kept would overwrite their sample
+//! with a partial total and are dropped.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::model::{MarketEvent, Side};
+
+/// Milliseconds kept per symbol and side, enough to take in fills that
+/// arrive late, e.g. from the old connection while a feed rotates.
+const WINDOW: usize = 256;
+
+/// Volume and notional of one symbol and side's millisecond, and the trades
+/// merged into it.
+#[derive(Default)]
+struct Bucket {
+    size: f64,
+    notional: f64,
+    trade_ids: HashSet<String>,
+}
+
+#[derive(Default)]
+pub struct Fills {
+    buckets: HashMap<(&'static str, String, Side), BTreeMap<i64, Bucket>>,
+}
+
+impl Fills {
+    /// Turns a trade or liquidation into the total of its millisecond so far.
+    /// Anything else is left alone. False for a fill that was already merged
+    /// or is too old to merge, which shouldn't be written.
+    pub fn merge(&mut self, event: &mut MarketEvent) -> bool {
+        let ts = event.ts();
+        let (kind, symbol, side, price, size, trade_id) = match event {
+            MarketEvent::Trade(trade) => {
+                ("TRADE", &trade.symbol, trade.side, &mut trade.price, &mut trade.size, trade.trade_id.as_ref())
+            }
+            MarketEvent::Liquidation(liquidation) => {
+                ("LIQUIDATION", &liquidation.symbol, liquidation.side, &mut liquidation.price, &mut liquidation.size, None)
+            }
+            _ => return true,
+        };
+        let buckets = self.buckets.entry((kind, symbol.clone(), side)).or_default();
+        if buckets.len() >= WINDOW && buckets.first_key_value().is_some_and(|(&first, _)| ts < first) {
+            return false;
+        }
+        let bucket = buckets.entry(ts).or_default();
+        if let Some(trade_id) = trade_id {
+            if !bucket.trade_ids.insert(trade_id.clone()) {
+                return false;
+            }
+        }
+        bucket.size += *size;
+        bucket.notional += *price * *size;
+        *size = bucket.size;
+        if bucket.size > 0.0 {
+            *price = bucket.notional / bucket.size;
+        }
+        if buckets.len() > WINDOW {
+            buckets.pop_first();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Trade, Venue};
+
+    fn trade(ts: i64, side: Side, price: f64, size: f64, trade_id: &str) -> MarketEvent {
+        MarketEvent::Trade(Trade {
+            venue: Venue::Bybit,
+            symbol: "BTC-USDT".to_string(),
+            exchange_ts: Some(ts),
+            local_ts: ts + 3,
+            side,
+            price,
+            size,
+            trade_id: Some(trade_id.to_string()),
+        })
+    }
+
+    fn merged(fills: &mut Fills, mut event: MarketEvent) -> Option<(f64, f64)> {
+        if !fills.merge(&mut event) {
+            return None;
+        }
+        match event {
+            MarketEvent::Trade(trade) => Some((trade.price, trade.size)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn same_millisecond_fills_accumulate_at_vwap() {
+        let mut fills = Fills::default();
+        assert_eq!(merged(&mut fills, trade(1000, Side::Buy, 100.0, 1.0, "1")), Some((100.0, 1.0)));
+        assert_eq!(merged(&mut fills, trade(1000, Side::Buy, 103.0, 2.0, "2")), Some((102.0, 3.0)));
+        // The other side and the next millisecond start their own totals.
+        assert_eq!(merged(&mut fills, trade(1000, Side::Sell, 99.0, 0.5, "3")), Some((99.0, 0.5)));
+        assert_eq!(merged(&mut fills, trade(1001, Side::Buy, 104.0, 1.0, "4")), Some((104.0, 1.0)));
+        // A late fill still adds to its own millisecond.
+        assert_eq!(merged(&mut fills, trade(1000, Side::Buy, 102.0, 1.0, "5")), Some((102.0, 4.0)));
+    }
+
+    #[test]
+    fn replayed_fills_are_merged_once() {
+        let mut fills = Fills::default();
+        assert_eq!(merged(&mut fills, trade(1000, Side::Buy, 100.0, 1.0, "1")), Some((100.0, 1.0)));
+        assert_eq!(merged(&mut fills, trade(1001, Side::Buy, 101.0, 2.0, "2")), Some((101.0, 2.0)));
+        // Sent again after a reconnect.
+        assert_eq!(merged(&mut fills, trade(1000, Side::Buy, 100.0, 1.0, "1")), None);
+        assert_eq!(merged(&mut fills, trade(1001, Side::Buy, 101.0, 2.0, "2")), None);
+        for ts in 2000..2000 + WINDOW as i64 {
+            merged(&mut fills, trade(ts, Side::Buy, 100.0, 1.0, &ts.to_string()));
+        }
+        assert_eq!(merged(&mut fills, trade(1999, Side::Buy, 100.0, 1.0, "1999")), None);
+    }
+}
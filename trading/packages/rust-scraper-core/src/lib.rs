@@ -0,0 +1,22 @@
+//! Shared plumbing for the Rust market data scrapers.
+//!
+//! Each scraper only needs to describe its exchange messages and how they map
+//! onto Redis time series; the socket loop, Redis connection and time helpers
+//! live here.
+
+pub mod adapter;
+pub mod backoff;
+pub mod book;
+pub mod config;
+pub mod de;
+pub mod depth;
+pub mod feed;
+pub mod fills;
+pub mod logging;
+pub mod metrics;
+pub mod model;
+pub mod queue;
+pub mod series;
+pub mod store;
+pub mod time;
+pub mod watchdog;
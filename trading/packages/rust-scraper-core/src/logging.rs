@@ -0,0 +1,52 @@
+//! Log setup: one JSON object per line with a UTC timestamp, level, target,
+//! the fields of the event and of the feed span it was logged from, so
+//! promtail can ship them to Loki as is.
+//!
+//! - `SCRAPER_LOG`: filter directives, e.g. `warn,rust_scraper_core::feed=debug`, defaults to `info`
+//! - `SCRAPER_LOG_FORMAT`: `json` (default) or `text` for human readable local runs
+
+use std::collections::HashMap;
+use std::env;
+use std::time::{Duration, Instant};
+use tracing_subscriber::EnvFilter;
+
+pub fn init() {
+    let filter = EnvFilter::try_from_env("SCRAPER_LOG").unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match env::var("SCRAPER_LOG_FORMAT").as_deref() {
+        Ok("text") => builder.init(),
+        _ => builder.json().flatten_event(true).with_current_span(true).with_span_list(false).init(),
+    }
+}
+
+/// Lets one log line per key through every `interval` and counts the rest,
+/// so an error repeated on every message doesn't flood the logs.
+pub struct RateLimit {
+    interval: Duration,
+    last: HashMap<String, (Instant, u64)>,
+}
+
+impl RateLimit {
+    pub fn new(interval: Duration) -> Self {
+        RateLimit { interval, last: HashMap::new() }
+    }
+
+    /// `Some` with the number of suppressed occurrences if `key` should be
+    /// logged now, `None` if it was logged too recently.
+    pub fn check(&mut self, key: &str) -> Option<u64> {
+        match self.last.get_mut(key) {
+            Some((logged, suppressed)) if logged.elapsed() < self.interval => {
+                *suppressed += 1;
+                None
+            }
+            Some((logged, suppressed)) => {
+                *logged = Instant::now();
+                Some(std::mem::take(suppressed))
+            }
+            None => {
+                self.last.insert(key.to_string(), (Instant::now(), 0));
+                Some(0)
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Prometheus metrics for the feeds and Redis writes, served in the text
+//! format on `/metrics`.
+//!
+//! Feed metrics are labelled with the feed name (`venue-channel`), Redis
+//! metrics with the exchange.
+
+use prometheus::{
+    register_gauge_vec, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec, Encoder, GaugeVec,
+    HistogramVec, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::model::MarketEvent;
+
+static MESSAGES_RECEIVED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("scraper_messages_received_total", "Text frames received", &["feed"]).unwrap()
+});
+
+static PARSE_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("scraper_parse_failures_total", "Text frames that couldn't be parsed", &["feed"]).unwrap()
+});
+
+static RECONNECTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("scraper_reconnects_total", "Connections re-established after being lost", &["feed"])
+        .unwrap()
+});
+
+static LAST_MESSAGE_AGE: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!("scraper_last_message_age_seconds", "Seconds since the last text frame", &["feed"]).unwrap()
+});
+
+static EXCHANGE_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "scraper_exchange_latency_seconds",
+        "Receive time minus exchange timestamp, for events that carry one",
+        &["feed"],
+        vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .unwrap()
+});
+
+static SEQUENCE_GAPS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "scraper_sequence_gaps_total",
+        "Holes an adapter found in the venue's sequence numbers or trade ids",
+        &["feed"]
+    )
+    .unwrap()
+});
+
+static REDIS_WRITE_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("scraper_redis_write_errors_total", "Redis writes that failed, per key", &["exchange"]).unwrap()
+});
+
+static REDIS_WRITE_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "scraper_redis_write_seconds",
+        "Duration of Redis round trips, one per pipeline",
+        &["exchange"],
+        vec![0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0]
+    )
+    .unwrap()
+});
+
+static QUEUE_DEPTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!("scraper_queue_depth", "Items waiting between two stages of a feed", &["feed", "queue"])
+        .unwrap()
+});
+
+static QUEUE_DROPPED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("scraper_queue_dropped_total", "Items dropped because a queue was full", &["feed", "queue"])
+        .unwrap()
+});
+
+/// When each feed last received a frame; turned into ages on every scrape so
+/// a dead feed keeps ageing rather than reporting its last value.
+static LAST_MESSAGE: LazyLock<Mutex<HashMap<String, Instant>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn message_received(feed: &str) {
+    MESSAGES_RECEIVED.with_label_values(&[feed]).inc();
+    LAST_MESSAGE.lock().unwrap().insert(feed.to_string(), Instant::now());
+}
+
+pub fn parse_failed(feed: &str) {
+    PARSE_FAILURES.with_label_values(&[feed]).inc();
+}
+
+pub fn reconnected(feed: &str) {
+    RECONNECTS.with_label_values(&[feed]).inc();
+}
+
+pub fn event_parsed(feed: &str, event: &MarketEvent) {
+    if let MarketEvent::Gap(_) = event {
+        SEQUENCE_GAPS.with_label_values(&[feed]).inc();
+    }
+    if let Some(exchange_ts) = event.exchange_ts() {
+        // Clocks drift, don't let a venue stamping from the future go negative.
+        let latency = (event.local_ts() - exchange_ts).max(0) as f64 / 1000.0;
+        EXCHANGE_LATENCY.with_label_values(&[feed]).observe(latency);
+    }
+}
+
+pub fn redis_latency(exchange: &str, duration: Duration) {
+    REDIS_WRITE_LATENCY.with_label_values(&[exchange]).observe(duration.as_secs_f64());
+}
+
+pub fn redis_error(exchange: &str) {
+    REDIS_WRITE_ERRORS.with_label_values(&[exchange]).inc();
+}
+
+pub fn queue_depth(labels: &[String; 2], depth: usize) {
+    QUEUE_DEPTH.with_label_values(&[&labels[0], &labels[1]]).set(depth as i64);
+}
+
+pub fn queue_dropped(labels: &[String; 2]) {
+    QUEUE_DROPPED.with_label_values(&[&labels[0], &labels[1]]).inc();
+}
+
+/// Every registered metric in the Prometheus text format.
+pub fn render() -> String {
+    for (feed, last_message) in LAST_MESSAGE.lock().unwrap().iter() {
+        LAST_MESSAGE_AGE.with_label_values(&[feed]).set(last_message.elapsed().as_secs_f64());
+    }
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+fn respond(stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers, the request has no body worth reading.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let (status, content_type, body) = match request_line.split_whitespace().nth(1) {
+        Some("/metrics") => ("200 OK", TextEncoder::new().format_type().to_string(), render()),
+        _ => ("404 Not Found", "text/plain".to_string(), "Not Found\n".to_string()),
+    };
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Serves `/metrics` on `listen` from a background thread.
+pub fn serve(listen: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(listen)?;
+    info!(address = %listener.local_addr()?, "Serving metrics");
+    thread::Builder::new().name("metrics".to_string()).spawn(move || {
+        for stream in listener.incoming() {
+            if let Err(e) = stream.and_then(respond) {
+                warn!(error = %e, "Metrics request failed");
+            }
+        }
+    })?;
+    Ok(())
+}
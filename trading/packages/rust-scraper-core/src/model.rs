@@ -0,0 +1,345 @@
+//! Venue independent market data produced by every adapter.
+//!
+//! Timestamps are epoch millis. `exchange_ts` is whatever the venue stamped on
+//! the message (if anything) and `local_ts` is when we received it.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Venue {
+    Bitmex,
+    Binance,
+    Coinbase,
+    Bybit,
+}
+
+impl Venue {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Venue::Bitmex => "BITMEX",
+            Venue::Binance => "BINANCE",
+            Venue::Coinbase => "COINBASE",
+            Venue::Bybit => "BYBIT",
+        }
+    }
+}
+
+impl fmt::Display for Venue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    /// Accepts the spellings venues use for sides: `Buy`, `sell`, `bid`, `ask`...
+    pub fn parse(side: &str) -> Option<Side> {
+        match side.to_ascii_lowercase().as_str() {
+            "buy" | "bid" | "bids" => Some(Side::Buy),
+            "sell" | "ask" | "asks" | "offer" => Some(Side::Sell),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Buy => "BUY",
+            Side::Sell => "SELL",
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+const QUOTE_ASSETS: [&str; 9] = ["FDUSD", "USDT", "USDC", "BUSD", "USD", "EUR", "GBP", "BTC", "ETH"];
+
+fn canonical_asset(asset: &str) -> &str {
+    match asset {
+        "XBT" => "BTC",
+        _ => asset,
+    }
+}
+
+/// Normalizes a venue symbol to `BASE-QUOTE`, e.g. `XBTUSD` and `BTC-USD` both
+/// become `BTC-USD`, `btcusdt` becomes `BTC-USDT`. Symbols that can't be split
+/// are returned upper cased.
+pub fn canonical_symbol(symbol: &str) -> String {
+    let symbol = symbol.to_ascii_uppercase();
+    let (base, quote) = match symbol.split_once(['-', '/', '_']) {
+        Some(pair) => pair,
+        None => match QUOTE_ASSETS
+            .iter()
+            .find(|quote| symbol.len() > quote.len() && symbol.ends_with(*quote))
+        {
+            Some(quote) => symbol.split_at(symbol.len() - quote.len()),
+            None => return symbol,
+        },
+    };
+    format!("{}-{}", canonical_asset(base), canonical_asset(quote))
+}
+
+/// Symbol for a perpetual swap on `symbol`, e.g. `BTCUSDT` becomes
+/// `BTC-USDT-PERP`, so it doesn't share series with the spot pair.
+pub fn perpetual_symbol(symbol: &str) -> String {
+    format!("{}-PERP", canonical_symbol(symbol))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BookLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub bid_price: f64,
+    pub bid_size: f64,
+    pub ask_price: f64,
+    pub ask_size: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    /// Aggressor side.
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+    pub trade_id: Option<String>,
+}
+
+/// Last traded price and rolling volume from a venue's ticker channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ticker {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub last_price: f64,
+    /// Base asset volume over the last 24 hours.
+    pub volume_24h: Option<f64>,
+}
+
+/// A change to a single price level. A size of zero removes the level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookDelta {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+}
+
+/// The full book, replacing anything previously known for the symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookSnapshot {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+}
+
+/// Size resting within `bps` basis points of the mid on each side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthBand {
+    pub bps: u32,
+    pub bid_size: f64,
+    pub ask_size: f64,
+}
+
+/// Top of book derived from a local book, so it always agrees with the book's
+/// own series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookTop {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub bid: BookLevel,
+    pub ask: BookLevel,
+    pub mid: f64,
+    pub spread: f64,
+    pub bands: Vec<DepthBand>,
+}
+
+/// The top levels of a book sampled at a fixed interval, so a backtest can
+/// load the book at a point in time without replaying every level's series.
+/// `seq` counts the samples of the symbol's book since the feed started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthSnapshot {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub seq: u64,
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+}
+
+/// Mark and index price of a derivative, what funding and liquidations are
+/// worked out from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkPrice {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub mark_price: f64,
+    pub index_price: Option<f64>,
+}
+
+/// Contract state from a venue's instrument feed. Venues only send what
+/// changed, so anything a message leaves out is `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub mark_price: Option<f64>,
+    pub index_price: Option<f64>,
+    pub open_interest: Option<f64>,
+    /// Rate of the upcoming funding.
+    pub funding_rate: Option<f64>,
+    /// Mark price less the index price.
+    pub fair_basis: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingRate {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub rate: f64,
+    pub next_funding_ts: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Liquidation {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    /// Side of the liquidation order.
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+}
+
+/// A stretch without data for the symbol, e.g. while its feed was
+/// reconnecting. `from_ts` is the last receive time before the hole and
+/// `to_ts` the first after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gap {
+    pub venue: Venue,
+    pub symbol: String,
+    pub exchange_ts: Option<i64>,
+    pub local_ts: i64,
+    pub from_ts: i64,
+    pub to_ts: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketEvent {
+    Quote(Quote),
+    Trade(Trade),
+    Ticker(Ticker),
+    BookDelta(BookDelta),
+    BookSnapshot(BookSnapshot),
+    DepthSnapshot(DepthSnapshot),
+    BookTop(BookTop),
+    MarkPrice(MarkPrice),
+    Instrument(Instrument),
+    FundingRate(FundingRate),
+    Liquidation(Liquidation),
+    Gap(Gap),
+}
+
+impl MarketEvent {
+    pub fn venue(&self) -> Venue {
+        match self {
+            MarketEvent::Quote(e) => e.venue,
+            MarketEvent::Trade(e) => e.venue,
+            MarketEvent::Ticker(e) => e.venue,
+            MarketEvent::BookDelta(e) => e.venue,
+            MarketEvent::BookSnapshot(e) => e.venue,
+            MarketEvent::DepthSnapshot(e) => e.venue,
+            MarketEvent::BookTop(e) => e.venue,
+            MarketEvent::MarkPrice(e) => e.venue,
+            MarketEvent::Instrument(e) => e.venue,
+            MarketEvent::FundingRate(e) => e.venue,
+            MarketEvent::Liquidation(e) => e.venue,
+            MarketEvent::Gap(e) => e.venue,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketEvent::Quote(e) => &e.symbol,
+            MarketEvent::Trade(e) => &e.symbol,
+            MarketEvent::Ticker(e) => &e.symbol,
+            MarketEvent::BookDelta(e) => &e.symbol,
+            MarketEvent::BookSnapshot(e) => &e.symbol,
+            MarketEvent::DepthSnapshot(e) => &e.symbol,
+            MarketEvent::BookTop(e) => &e.symbol,
+            MarketEvent::MarkPrice(e) => &e.symbol,
+            MarketEvent::Instrument(e) => &e.symbol,
+            MarketEvent::FundingRate(e) => &e.symbol,
+            MarketEvent::Liquidation(e) => &e.symbol,
+            MarketEvent::Gap(e) => &e.symbol,
+        }
+    }
+
+    /// The exchange timestamp when there is one, otherwise the receive time.
+    pub fn ts(&self) -> i64 {
+        let (exchange_ts, local_ts) = self.timestamps();
+        exchange_ts.unwrap_or(local_ts)
+    }
+
+    pub fn exchange_ts(&self) -> Option<i64> {
+        self.timestamps().0
+    }
+
+    pub fn local_ts(&self) -> i64 {
+        self.timestamps().1
+    }
+
+    fn timestamps(&self) -> (Option<i64>, i64) {
+        match self {
+            MarketEvent::Quote(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::Trade(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::Ticker(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::BookDelta(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::BookSnapshot(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::DepthSnapshot(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::BookTop(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::MarkPrice(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::Instrument(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::FundingRate(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::Liquidation(e) => (e.exchange_ts, e.local_ts),
+            MarketEvent::Gap(e) => (e.exchange_ts, e.local_ts),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use tokio::sync::mpsc;
+
+use crate::config::{Overflow, PipelineConfig};
+use crate::metrics;
+
+/// The receiving stage has gone away.
+#[derive(Debug)]
+pub struct Closed;
+
+/// A bounded queue between two stages of a feed, reporting its depth and
+/// drops as `scraper_queue_*{feed, queue}`.
+pub fn queue<T>(feed: &str, name: &'static str, config: &PipelineConfig) -> (QueueSender<T>, QueueReceiver<T>) {
+    let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+    let labels = [feed.to_string(), name.to_string()];
+    (
+        QueueSender { tx, overflow: config.overflow, labels: labels.clone() },
+        QueueReceiver { rx, labels },
+    )
+}
+
+pub struct QueueSender<T> {
+    tx: mpsc::Sender<T>,
+    overflow: Overflow,
+    labels: [String; 2],
+}
+
+impl<T> QueueSender<T> {
+    /// Queues `item` according to the overflow policy. `Ok(false)` means it
+    /// was dropped because the queue was full.
+    pub async fn send(&self, item: T) -> Result<bool, Closed> {
+        let sent = match self.overflow {
+            Overflow::Block => self.tx.send(item).await.map(|_| true).map_err(|_| Closed)?,
+            Overflow::Drop => match self.tx.try_send(item) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => false,
+                Err(mpsc::error::TrySendError::Closed(_)) => return Err(Closed),
+            },
+        };
+        if !sent {
+            metrics::queue_dropped(&self.labels);
+        }
+        metrics::queue_depth(&self.labels, self.tx.max_capacity() - self.tx.capacity());
+        Ok(sent)
+    }
+}
+
+pub struct QueueReceiver<T> {
+    rx: mpsc::Receiver<T>,
+    labels: [String; 2],
+}
+
+impl<T> QueueReceiver<T> {
+    pub async fn recv(&mut self) -> Option<T> {
+        let item = self.rx.recv().await;
+        metrics::queue_depth(&self.labels, self.rx.len());
+        item
+    }
+}
@@ -0,0 +1,197 @@
+//! Maps market events onto the RedisTimeSeries key layout
+//! `{PREFIX}:{SUB}:{SIDE}:{FIELD}`, e.g. `BITMEX:XBTUSD:QUOTE:BUY:PRICE` with
+//! the default `{venue}:{pair}` prefix template.
+//!
+//! Depth snapshots are the exception: each is one entry of the
+//! `{PREFIX}:BOOK:SNAPSHOT` stream, with id `{ts}-{seq}` and the levels as JSON
+//! `[[price, size], ...]` best first, so `XREVRANGE key T - COUNT 1` is the
+//! book at T.
+
+use crate::model::{BookLevel, MarketEvent, Side};
+
+/// Levels as `[[price, size], ...]`.
+fn levels_json(levels: &[BookLevel]) -> String {
+    let levels: Vec<(f64, f64)> = levels.iter().map(|level| (level.price, level.size)).collect();
+    serde_json::to_string(&levels).unwrap_or_default()
+}
+
+/// A single Redis operation derived from an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeriesWrite {
+    /// A time series sample, created with `labels` if the key is new.
+    Sample {
+        key: String,
+        ts: i64,
+        value: f64,
+        labels: Vec<(&'static str, String)>,
+    },
+    /// A stream entry with id `{ts}-{seq}`.
+    Entry {
+        key: String,
+        ts: i64,
+        seq: u64,
+        fields: Vec<(&'static str, String)>,
+    },
+}
+
+impl SeriesWrite {
+    fn sample(key: String, ts: i64, value: f64, labels: &[(&'static str, &str)]) -> Self {
+        SeriesWrite::Sample {
+            key,
+            ts,
+            value,
+            labels: labels.iter().map(|(name, value)| (*name, value.to_string())).collect(),
+        }
+    }
+}
+
+/// Pair as used in keys: the canonical symbol without the dash and with BTC
+/// spelled XBT, so BitMEX's `XBTUSD` series keep their keys. Venues whose
+/// old scrapers hardcoded `XBTUSD` for another pair or channel now write
+/// under their real pair, see "Key changes" in the README.
+pub fn pair(symbol: &str) -> String {
+    symbol
+        .split('-')
+        .map(|asset| if asset == "BTC" { "XBT" } else { asset })
+        .collect()
+}
+
+/// Collects the writes for one event, which all share a key prefix, symbol and timestamp.
+struct Writes<'a> {
+    prefix: String,
+    symbol: &'a str,
+    ts: i64,
+    writes: Vec<SeriesWrite>,
+}
+
+impl Writes<'_> {
+    fn sample(&mut self, suffix: String, value: f64, labels: &[(&'static str, &str)]) {
+        let mut labels = labels.to_vec();
+        labels.push(("SYMBOL", self.symbol));
+        self.writes.push(SeriesWrite::sample(
+            format!("{}:{}", self.prefix, suffix),
+            self.ts,
+            value,
+            &labels,
+        ));
+    }
+
+    fn side(&mut self, sub: &str, side: Side, price: f64, size: f64) {
+        let side = side.as_str();
+        self.sample(format!("{}:{}:PRICE", sub, side), price, &[("SIDE", side), ("SUB", sub), ("GROUP", "PRICE")]);
+        self.sample(format!("{}:{}:VOL", sub, side), size, &[("SIDE", side), ("SUB", sub), ("GROUP", "VOL")]);
+    }
+
+    fn level(&mut self, side: Side, level: BookLevel) {
+        self.sample(format!("BOOK:{}:{}", side, level.price), level.size, &[("SIDE", side.as_str()), ("SUB", "BOOK")]);
+    }
+}
+
+/// Expands `{venue}`, `{pair}` and `{symbol}` in a key template.
+pub fn key_prefix(key_template: &str, event: &MarketEvent) -> String {
+    key_template
+        .replace("{venue}", event.venue().as_str())
+        .replace("{pair}", &pair(event.symbol()))
+        .replace("{symbol}", event.symbol())
+}
+
+/// Everything that should be written to Redis for `event`.
+pub fn series_writes(event: &MarketEvent, key_template: &str) -> Vec<SeriesWrite> {
+    let mut writes = Writes {
+        prefix: key_prefix(key_template, event),
+        symbol: event.symbol(),
+        ts: event.ts(),
+        writes: Vec::new(),
+    };
+    match event {
+        MarketEvent::Quote(quote) => {
+            writes.side("QUOTE", Side::Buy, quote.bid_price, quote.bid_size);
+            writes.side("QUOTE", Side::Sell, quote.ask_price, quote.ask_size);
+        }
+        MarketEvent::Trade(trade) => writes.side("TRADE", trade.side, trade.price, trade.size),
+        MarketEvent::Ticker(ticker) => {
+            writes.sample("TICKER:LAST:PRICE".to_string(), ticker.last_price, &[("SUB", "TICKER"), ("GROUP", "PRICE")]);
+            if let Some(volume_24h) = ticker.volume_24h {
+                writes.sample("TICKER:24H:VOL".to_string(), volume_24h, &[("SUB", "TICKER"), ("GROUP", "VOL")]);
+            }
+        }
+        MarketEvent::BookDelta(delta) => {
+            writes.level(delta.side, BookLevel { price: delta.price, size: delta.size });
+        }
+        // Levels the snapshot dropped come as zero size deltas before it,
+        // see `OrderBook::snapshot_events`.
+        MarketEvent::BookSnapshot(snapshot) => {
+            for level in &snapshot.bids {
+                writes.level(Side::Buy, *level);
+            }
+            for level in &snapshot.asks {
+                writes.level(Side::Sell, *level);
+            }
+        }
+        MarketEvent::DepthSnapshot(depth) => writes.writes.push(SeriesWrite::Entry {
+            key: format!("{}:BOOK:SNAPSHOT", writes.prefix),
+            ts: writes.ts,
+            seq: depth.seq,
+            fields: vec![
+                ("seq", depth.seq.to_string()),
+                ("local_ts", depth.local_ts.to_string()),
+                ("bids", levels_json(&depth.bids)),
+                ("asks", levels_json(&depth.asks)),
+            ],
+        }),
+        MarketEvent::BookTop(top) => {
+            writes.side("TOP", Side::Buy, top.bid.price, top.bid.size);
+            writes.side("TOP", Side::Sell, top.ask.price, top.ask.size);
+            writes.sample("TOP:MID:PRICE".to_string(), top.mid, &[("SUB", "TOP"), ("GROUP", "PRICE")]);
+            writes.sample("TOP:SPREAD".to_string(), top.spread, &[("SUB", "TOP"), ("GROUP", "SPREAD")]);
+            for band in &top.bands {
+                let bps = band.bps.to_string();
+                for (side, size) in [(Side::Buy, band.bid_size), (Side::Sell, band.ask_size)] {
+                    writes.sample(
+                        format!("DEPTH:{}BPS:{}:VOL", bps, side),
+                        size,
+                        &[("SIDE", side.as_str()), ("SUB", "DEPTH"), ("GROUP", "VOL"), ("BAND", &bps)],
+                    );
+                }
+            }
+        }
+        MarketEvent::MarkPrice(mark) => {
+            writes.sample("MARK:PRICE".to_string(), mark.mark_price, &[("SUB", "MARK"), ("GROUP", "PRICE")]);
+            if let Some(index_price) = mark.index_price {
+                writes.sample("INDEX:PRICE".to_string(), index_price, &[("SUB", "INDEX"), ("GROUP", "PRICE")]);
+            }
+        }
+        MarketEvent::Instrument(instrument) => {
+            let fields = [
+                ("MARK_PRICE", "PRICE", instrument.mark_price),
+                ("INDEX_PRICE", "PRICE", instrument.index_price),
+                ("OPEN_INTEREST", "VOL", instrument.open_interest),
+                ("FUNDING_RATE", "RATE", instrument.funding_rate),
+                ("FAIR_BASIS", "PRICE", instrument.fair_basis),
+            ];
+            for (field, group, value) in fields {
+                if let Some(value) = value {
+                    writes.sample(
+                        format!("INSTRUMENT:{}", field),
+                        value,
+                        &[("SUB", "INSTRUMENT"), ("FIELD", field), ("GROUP", group)],
+                    );
+                }
+            }
+        }
+        MarketEvent::FundingRate(funding) => {
+            writes.sample("FUNDING:RATE".to_string(), funding.rate, &[("SUB", "FUNDING"), ("GROUP", "RATE")]);
+            if let Some(next_funding_ts) = funding.next_funding_ts {
+                writes.sample("FUNDING:NEXT".to_string(), next_funding_ts as f64, &[("SUB", "FUNDING"), ("GROUP", "NEXT")]);
+            }
+        }
+        MarketEvent::Liquidation(liquidation) => {
+            writes.side("LIQUIDATION", liquidation.side, liquidation.price, liquidation.size);
+        }
+        // Stamped when data resumed, with the length of the hole in millis.
+        MarketEvent::Gap(gap) => {
+            writes.sample("GAP".to_string(), (gap.to_ts - gap.from_ts) as f64, &[("SUB", "GAP"), ("GROUP", "DURATION")]);
+        }
+    }
+    writes.writes
+}
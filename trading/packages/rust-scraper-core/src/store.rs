@@ -0,0 +1,195 @@
+use redis::aio::ConnectionManager;
+use redis::{cmd, Cmd, RedisError, RedisResult};
+use redis_ts::{TsDuplicatePolicy, TsOptions};
+use std::time::{Duration, Instant};
+use tracing::error;
+
+use crate::config::{Config, RedisConfig};
+use crate::logging::RateLimit;
+use crate::metrics;
+use crate::model::MarketEvent;
+use crate::series::{series_writes, SeriesWrite};
+
+/// A connection that re-establishes itself after Redis restarts.
+pub async fn connect(config: &RedisConfig) -> RedisResult<ConnectionManager> {
+    let redis_password = config.password.as_ref().expect("$REDIS_PASSWORD is not set");
+    let connection_string = format!("redis://default:{}@{}:{}", redis_password, config.host, config.port);
+    let client = redis::Client::open(connection_string)?;
+    client.get_connection_manager().await
+}
+
+/// Writes samples into RedisTimeSeries, creating each series on first use with
+/// the exchange label, retention and a last-wins duplicate policy.
+///
+/// Depth snapshots go to streams trimmed to the same retention.
+///
+/// Samples are queued and sent as one pipeline per `write`, or per
+/// `batch_window` when one is set, instead of a round trip per sample.
+pub struct RedisWriter {
+    con: ConnectionManager,
+    exchange: String,
+    options: TsOptions,
+    retention_time: u64,
+    key_template: String,
+    errors: RateLimit,
+    batch_window: Option<Duration>,
+    /// Queued `TS.ADD`s and `XADD`s, and when the oldest was queued.
+    pending: Vec<Queued>,
+    pending_since: Option<Instant>,
+}
+
+/// A queued command, with the entry id it adds for an `XADD`.
+struct Queued {
+    key: String,
+    entry_id: Option<String>,
+    add: Cmd,
+}
+
+/// Whether an `XADD` failed because the stream already has an entry at or
+/// after its id.
+fn id_rejected(e: &RedisError) -> bool {
+    e.code() == Some("ERR") && e.detail().is_some_and(|detail| detail.contains("equal or smaller"))
+}
+
+impl RedisWriter {
+    pub fn new(con: ConnectionManager, exchange: &str, retention_time: u64, key_template: &str) -> Self {
+        let options = TsOptions::default()
+            .duplicate_policy(TsDuplicatePolicy::Last)
+            .retention_time(retention_time)
+            .label("EXCHANGE", exchange);
+        RedisWriter {
+            con,
+            exchange: exchange.to_string(),
+            options,
+            retention_time,
+            key_template: key_template.to_string(),
+            errors: RateLimit::new(Duration::from_secs(10)),
+            batch_window: None,
+            pending: Vec::new(),
+            pending_since: None,
+        }
+    }
+
+    pub async fn connect(config: &Config, exchange: &str) -> RedisResult<Self> {
+        Ok(Self::new(connect(&config.redis).await?, exchange, config.retention, &config.key_template)
+            .batch_window(config.redis.batch_ms))
+    }
+
+    /// Holds samples for up to `batch_ms` before sending them, 0 sends every
+    /// `write` straight away.
+    pub fn batch_window(mut self, batch_ms: u64) -> Self {
+        self.batch_window = (batch_ms > 0).then(|| Duration::from_millis(batch_ms));
+        self
+    }
+
+    /// Queues a sample for `key`, created with `labels` if it doesn't exist yet.
+    fn queue(&mut self, key: String, ts: i64, value: f64, labels: &[(&str, String)]) {
+        let mut options = self.options.clone();
+        for (name, value) in labels {
+            options = options.label(name, value);
+        }
+        let mut add = cmd("TS.ADD");
+        add.arg(&key).arg(ts).arg(value).arg(options);
+        self.pending.push(Queued { key, entry_id: None, add });
+        self.pending_since.get_or_insert_with(Instant::now);
+    }
+
+    /// Queues an `XADD` of entry `{ts}-{seq}`, trimming entries older than
+    /// the retention.
+    fn queue_entry(&mut self, key: String, ts: i64, seq: u64, fields: &[(&str, String)]) {
+        let entry_id = format!("{}-{}", ts, seq);
+        let mut add = cmd("XADD");
+        add.arg(&key)
+            .arg("MINID")
+            .arg("~")
+            .arg(ts.saturating_sub(self.retention_time as i64).max(0))
+            .arg(&entry_id);
+        for (name, value) in fields {
+            add.arg(*name).arg(value);
+        }
+        self.pending.push(Queued { key, entry_id: Some(entry_id), add });
+        self.pending_since.get_or_insert_with(Instant::now);
+    }
+
+    fn key_failed(&mut self, key: &str, e: &RedisError) {
+        metrics::redis_error(&self.exchange);
+        if let Some(suppressed) = self.errors.check(e.category()) {
+            error!(key, error = %e, kind = e.category(), suppressed, "Error adding to redis");
+        }
+    }
+
+    /// Whether the stream at `key` ends with entry `entry_id`, i.e. a replayed
+    /// `XADD` was rejected because the pipeline already wrote it rather than
+    /// because the stream had moved past it.
+    async fn already_added(&mut self, key: &str, entry_id: &str) -> bool {
+        let last: RedisResult<Vec<(String, Vec<String>)>> =
+            cmd("XREVRANGE").arg(key).arg("+").arg("-").arg("COUNT").arg(1).query_async(&mut self.con).await;
+        matches!(last.as_deref(), Ok([(id, _)]) if id == entry_id)
+    }
+
+    /// Sends everything queued as a single pipeline.
+    ///
+    /// A failed pipeline only reports its first error, so unless the
+    /// connection itself broke the commands are replayed one by one to find
+    /// the keys that were rejected. Replaying a `TS.ADD` is safe with the
+    /// last-wins duplicate policy; an `XADD` the pipeline already applied is
+    /// rejected for its explicit id and only counts as written if the
+    /// stream's last entry is that id.
+    pub async fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let batch = std::mem::take(&mut self.pending);
+        self.pending_since = None;
+        let mut pipe = redis::pipe();
+        for queued in &batch {
+            pipe.add_command(queued.add.clone()).ignore();
+        }
+        let start = Instant::now();
+        let redis_query: RedisResult<()> = pipe.query_async(&mut self.con).await;
+        metrics::redis_latency(&self.exchange, start.elapsed());
+        match redis_query {
+            Ok(()) => {}
+            Err(e) if e.is_io_error() => {
+                for queued in &batch {
+                    self.key_failed(&queued.key, &e);
+                }
+            }
+            Err(_) => {
+                for queued in batch {
+                    let redis_query: RedisResult<()> = queued.add.query_async(&mut self.con).await;
+                    let Err(e) = redis_query else {
+                        continue;
+                    };
+                    if let Some(entry_id) = queued.entry_id.as_deref().filter(|_| id_rejected(&e)) {
+                        if self.already_added(&queued.key, entry_id).await {
+                            continue;
+                        }
+                    }
+                    self.key_failed(&queued.key, &e);
+                }
+            }
+        }
+    }
+
+    /// When the queued samples are due to be sent, if any are queued.
+    pub fn flush_deadline(&self) -> Option<Instant> {
+        let since = self.pending_since?;
+        Some(since + self.batch_window.unwrap_or_default())
+    }
+
+    /// Writes every event parsed from one message.
+    pub async fn write(&mut self, events: &[MarketEvent]) {
+        for event in events {
+            for write in series_writes(event, &self.key_template) {
+                match write {
+                    SeriesWrite::Sample { key, ts, value, labels } => self.queue(key, ts, value, &labels),
+                    SeriesWrite::Entry { key, ts, seq, fields } => self.queue_entry(key, ts, seq, &fields),
+                }
+            }
+        }
+        if self.flush_deadline().is_some_and(|deadline| deadline <= Instant::now()) {
+            self.flush().await;
+        }
+    }
+}
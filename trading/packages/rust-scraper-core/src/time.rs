@@ -0,0 +1,22 @@
+use chrono::DateTime;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Milliseconds since the unix epoch according to the local clock.
+pub fn now_millis() -> i64 {
+    let since_the_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards");
+    since_the_epoch.as_millis() as i64
+}
+
+/// Parses an exchange timestamp such as `2024-01-03T00:09:50.444Z` into epoch millis.
+pub fn parse_millis(timestamp: &str) -> Option<i64> {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(datetime) => Some(datetime.timestamp_millis()),
+        Err(e) => {
+            warn!(timestamp, error = %e, "Failed to parse date-time string");
+            None
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config::WatchdogConfig;
+
+fn threshold(millis: u64) -> Option<Duration> {
+    match millis {
+        0 => None,
+        millis => Some(Duration::from_millis(millis)),
+    }
+}
+
+/// When a feed last produced market events, set by the stage that parses its
+/// frames and read by the socket reader's watchdog. Frames that parse to
+/// nothing, e.g. subscription acks and application level pongs, don't count,
+/// so a connection that only answers heartbeats still goes stale.
+#[derive(Debug, Clone)]
+pub struct LastData {
+    base: Instant,
+    /// Millis after `base`.
+    at: Arc<AtomicU64>,
+}
+
+impl LastData {
+    pub fn new() -> Self {
+        LastData { base: Instant::now(), at: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// A frame produced market events.
+    pub fn data(&self) {
+        self.at.store(self.base.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Instant {
+        self.base + Duration::from_millis(self.at.load(Ordering::Relaxed))
+    }
+}
+
+impl Default for LastData {
+    fn default() -> Self {
+        LastData::new()
+    }
+}
+
+/// Tracks when a connection last delivered data and answered a Ping, and
+/// declares it stale once either has been silent for too long.
+///
+/// A half-open TCP connection never errors on read, so without this a feed can
+/// sit on a dead socket indefinitely.
+pub struct Watchdog {
+    stale_after: Option<Duration>,
+    pong_timeout: Option<Duration>,
+    connected: Instant,
+    last_data: LastData,
+    last_pong: Instant,
+    ping_sent: Option<Instant>,
+}
+
+impl Watchdog {
+    /// Watches a connection opened now, whose data is reported to `last_data`.
+    pub fn new(config: &WatchdogConfig, last_data: LastData) -> Self {
+        Watchdog {
+            stale_after: threshold(config.stale_after_ms),
+            pong_timeout: threshold(config.pong_timeout_ms),
+            connected: Instant::now(),
+            last_data,
+            last_pong: Instant::now(),
+            ping_sent: None,
+        }
+    }
+
+    pub fn ping(&mut self) {
+        self.ping_sent.get_or_insert_with(Instant::now);
+    }
+
+    pub fn pong(&mut self) {
+        self.last_pong = Instant::now();
+        self.ping_sent = None;
+    }
+
+    /// Time since the last market data, or since connecting if there was none since.
+    pub fn since_data(&self) -> Duration {
+        self.last_data.get().max(self.connected).elapsed()
+    }
+
+    /// Whether a Ping is still waiting for its Pong.
+    pub fn awaiting_pong(&self) -> bool {
+        self.ping_sent.is_some()
+    }
+
+    /// Why the connection should be considered dead, if it should.
+    pub fn check(&self) -> Option<String> {
+        if let Some(stale_after) = self.stale_after {
+            let since_data = self.since_data();
+            if since_data >= stale_after {
+                return Some(format!("No data for {:?}", since_data));
+            }
+        }
+        if let (Some(pong_timeout), Some(ping_sent)) = (self.pong_timeout, self.ping_sent) {
+            if ping_sent.elapsed() >= pong_timeout {
+                return Some(format!(
+                    "No Pong {:?} after Ping, last one {:?} ago",
+                    ping_sent.elapsed(),
+                    self.last_pong.elapsed()
+                ));
+            }
+        }
+        None
+    }
+}